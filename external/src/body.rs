@@ -0,0 +1,76 @@
+/// Chunks of a response body, as bytes.
+pub type BodyChunks<S> = futures_util::stream::Map<
+    S,
+    fn(
+        Result<web_sys::wasm_bindgen::JsValue, web_sys::wasm_bindgen::JsValue>,
+    ) -> std::io::Result<Vec<u8>>,
+>;
+
+/// Reads the `Uint8Array` chunks of a response body, a failed chunk ends the games.
+pub fn body_chunks<S>(body: S) -> BodyChunks<S>
+where
+    S: futures_util::Stream<
+        Item = Result<web_sys::wasm_bindgen::JsValue, web_sys::wasm_bindgen::JsValue>,
+    >,
+{
+    use futures_util::StreamExt;
+    body.map(|chunk| {
+        chunk
+            .map(|chunk| web_sys::js_sys::Uint8Array::new(&chunk).to_vec())
+            .map_err(|error| {
+                std::io::Error::other(error.as_string().unwrap_or_else(|| format!("{error:?}")))
+            })
+    })
+}
+
+/// Defines a stream of the games of a PGN export, read from a response body as it
+/// downloads.
+///
+/// Games that can not be read are skipped, and a chunk that fails to download ends the
+/// stream. [`rooky_core::database::PgnStream`] over [`body_chunks`] gives every error.
+macro_rules! body_game_stream {
+    ($(#[$meta:meta])* $name:ident) => {
+        $(#[$meta])*
+        pub struct $name<S> {
+            inner: rooky_core::database::PgnStream<crate::BodyChunks<S>>,
+        }
+        impl<S> $name<S>
+        where
+            S: futures_util::Stream<
+                Item = Result<web_sys::wasm_bindgen::JsValue, web_sys::wasm_bindgen::JsValue>,
+            >,
+        {
+            pub fn new(stream: S) -> Self {
+                Self {
+                    inner: rooky_core::database::PgnStream::from_results(crate::body_chunks(
+                        stream,
+                    )),
+                }
+            }
+        }
+        impl<S> futures_util::Stream for $name<S>
+        where
+            S: futures_util::Stream<
+                    Item = Result<web_sys::wasm_bindgen::JsValue, web_sys::wasm_bindgen::JsValue>,
+                > + Unpin,
+        {
+            type Item = rooky_core::RookyGame;
+
+            fn poll_next(
+                self: std::pin::Pin<&mut Self>,
+                cx: &mut std::task::Context<'_>,
+            ) -> std::task::Poll<Option<Self::Item>> {
+                let this = self.get_mut();
+                loop {
+                    match std::task::ready!(std::pin::Pin::new(&mut this.inner).poll_next(cx)) {
+                        Some(Ok(game)) => return std::task::Poll::Ready(Some(game)),
+                        // Skip games that can not be read
+                        Some(Err(_)) => {}
+                        None => return std::task::Poll::Ready(None),
+                    }
+                }
+            }
+        }
+    };
+}
+pub(crate) use body_game_stream;
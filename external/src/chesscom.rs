@@ -72,7 +72,7 @@ impl ChessComClient {
         web_sys::wasm_bindgen::JsValue,
     > {
         let response = self
-            .get(&format!("player/{username}/games/{year:04}/{month:02}/pgn",))
+            .get(&format!("player/{username}/games/{year:04}/{month:02}/pgn"))
             .await;
         match response {
             Ok(resp) => {
@@ -93,62 +93,10 @@ impl ChessComClient {
     }
 }
 
-pub struct ChessComGameStream<S> {
-    inner: S,
-    buffer: Vec<u8>,
-}
-
-impl<S> ChessComGameStream<S> {
-    pub const fn new(stream: S) -> Self {
-        Self {
-            inner: stream,
-            buffer: Vec::new(),
-        }
-    }
-}
-impl<S> futures_util::Stream for ChessComGameStream<S>
-where
-    S: futures_util::Stream<
-            Item = Result<web_sys::wasm_bindgen::JsValue, web_sys::wasm_bindgen::JsValue>,
-        > + Unpin,
-{
-    type Item = rooky_core::RookyGame;
-
-    fn poll_next(
-        self: std::pin::Pin<&mut Self>,
-        cx: &mut std::task::Context<'_>,
-    ) -> std::task::Poll<Option<Self::Item>> {
-        use std::task::Poll;
-        let this = self.get_mut();
-
-        loop {
-            // Try to extract a complete game first
-            if let Some(pos) = this.buffer.windows(3).position(|w| w == b"\n\n\n") {
-                let (game_bytes, rest) = this.buffer.split_at(pos + 3);
-                if let Ok(game) = rooky_core::RookyGame::try_from(game_bytes) {
-                    this.buffer = rest.to_vec();
-                    return Poll::Ready(Some(game));
-                }
-                this.buffer = rest.to_vec();
-                continue;
-            }
-
-            // No complete game in buffer yet: try to poll underlying stream
-            match std::pin::Pin::new(&mut this.inner).poll_next(cx) {
-                Poll::Ready(Some(Ok(chunk))) => {
-                    let new_bytes = web_sys::js_sys::Uint8Array::new(&chunk).to_vec();
-                    this.buffer.extend_from_slice(&new_bytes);
-                }
-                Poll::Ready(Some(Err(_))) => {} // skip error chunks
-                Poll::Ready(None) => {
-                    // Stream ended
-                    return Poll::Ready(None);
-                }
-                Poll::Pending => return Poll::Pending,
-            }
-        }
-    }
-}
+crate::body_game_stream!(
+    /// Games of a Chess.com export, read as it downloads.
+    ChessComGameStream
+);
 
 #[cfg(test)]
 mod tests {
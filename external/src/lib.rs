@@ -7,6 +7,8 @@
     clippy::nursery
 )]
 
+mod body;
+pub use body::*;
 mod lichess;
 pub use lichess::*;
 mod chesscom;
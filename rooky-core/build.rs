@@ -0,0 +1,74 @@
+//! Generates the ECO opening table from the TSV files in `src/openings`.
+//!
+//! Every line is replayed here, so a bad row fails the build instead of the application,
+//! and the library gets the table and its position index as plain statics.
+
+use shakmaty::zobrist::ZobristHash;
+use shakmaty::Position;
+use std::fmt::Write;
+
+const OPENING_TABLES: [&str; 5] = ["a", "b", "c", "d", "e"];
+
+fn fail(path: &str, line: usize, reason: &str) -> ! {
+    panic!("{path}:{line}: {reason}")
+}
+
+fn main() {
+    let mut openings = String::from("[\n");
+    let mut positions = Vec::new();
+    for table in OPENING_TABLES {
+        let path = format!("src/openings/{table}.tsv");
+        println!("cargo:rerun-if-changed={path}");
+        let text =
+            std::fs::read_to_string(&path).unwrap_or_else(|e| fail(&path, 0, &e.to_string()));
+        // The first line of each table is its header
+        for (number, line) in text.lines().enumerate().skip(1) {
+            let line_number = number + 1;
+            let mut columns = line.split('\t');
+            let (Some(code), Some(full_name), Some(pgn), None) = (
+                columns.next(),
+                columns.next(),
+                columns.next(),
+                columns.next(),
+            ) else {
+                fail(&path, line_number, "expected the eco, name and pgn columns");
+            };
+            let (name, variation) = full_name
+                .split_once(':')
+                .map_or((full_name, ""), |(name, variation)| {
+                    (name.trim(), variation.trim())
+                });
+            let mut position = shakmaty::Chess::default();
+            for token in pgn.split_whitespace().filter(|token| !token.ends_with('.')) {
+                let chess_move = token
+                    .parse::<shakmaty::san::SanPlus>()
+                    .ok()
+                    .and_then(|san| san.san.to_move(&position).ok())
+                    .unwrap_or_else(|| fail(&path, line_number, &format!("illegal move {token}")));
+                position.play_unchecked(&chess_move);
+            }
+            let hash: shakmaty::zobrist::Zobrist64 =
+                position.zobrist_hash(shakmaty::EnPassantMode::Legal);
+            positions.push((hash.0, positions.len()));
+            writeln!(
+                openings,
+                "    EcoOpening {{ code: {code:?}, name: {name:?}, variation: {variation:?}, pgn: {pgn:?} }},"
+            )
+            .unwrap();
+        }
+    }
+    openings.push(']');
+
+    // Sorted by hash for binary search, the first line reaching a position is kept.
+    positions.sort_by_key(|(hash, _)| *hash);
+    positions.dedup_by_key(|(hash, _)| *hash);
+    let mut index = String::from("[\n");
+    for (hash, number) in positions {
+        writeln!(index, "    ({hash:#018x}, {number}),").unwrap();
+    }
+    index.push(']');
+
+    let out_dir = std::path::PathBuf::from(std::env::var("OUT_DIR").unwrap());
+    std::fs::write(out_dir.join("eco_openings.rs"), openings).unwrap();
+    std::fs::write(out_dir.join("eco_positions.rs"), index).unwrap();
+}
@@ -0,0 +1,422 @@
+/// Colors used by the `%csl` and `%cal` commands to draw on the board.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AnnotationColor {
+    Green,
+    Red,
+    Yellow,
+    Blue,
+}
+impl TryFrom<char> for AnnotationColor {
+    type Error = crate::errors::ChessError;
+
+    fn try_from(value: char) -> Result<Self, Self::Error> {
+        match value {
+            'G' => Ok(Self::Green),
+            'R' => Ok(Self::Red),
+            'Y' => Ok(Self::Yellow),
+            'B' => Ok(Self::Blue),
+            _ => Err(crate::errors::ChessError::InvalidValue {
+                kind: "annotation color",
+                value: value.to_string(),
+            }),
+        }
+    }
+}
+impl std::fmt::Display for AnnotationColor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Green => write!(f, "G"),
+            Self::Red => write!(f, "R"),
+            Self::Yellow => write!(f, "Y"),
+            Self::Blue => write!(f, "B"),
+        }
+    }
+}
+
+/// A highlighted square, as written by `[%csl Gd4]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SquareHighlight {
+    pub color: AnnotationColor,
+    pub square: shakmaty::Square,
+}
+impl std::str::FromStr for SquareHighlight {
+    type Err = crate::errors::ChessError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        let color = chars
+            .next()
+            .map_or_else(|| Err(invalid_command(s)), TryInto::try_into)?;
+        let square = parse_square(chars.as_str())?;
+        Ok(Self { color, square })
+    }
+}
+impl std::fmt::Display for SquareHighlight {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.color, self.square)
+    }
+}
+
+/// An arrow between two squares, as written by `[%cal Ge2e4]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Arrow {
+    pub color: AnnotationColor,
+    pub from: shakmaty::Square,
+    pub to: shakmaty::Square,
+}
+impl std::str::FromStr for Arrow {
+    type Err = crate::errors::ChessError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        let color = chars
+            .next()
+            .map_or_else(|| Err(invalid_command(s)), TryInto::try_into)?;
+        let squares = chars.as_str();
+        if squares.len() != 4 || !squares.is_ascii() {
+            return Err(invalid_command(s));
+        }
+        let (from, to) = squares.split_at(2);
+        Ok(Self {
+            color,
+            from: parse_square(from)?,
+            to: parse_square(to)?,
+        })
+    }
+}
+impl std::fmt::Display for Arrow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}{}", self.color, self.from, self.to)
+    }
+}
+
+/// Engine evaluation from White's point of view, as written by `[%eval 0.17]` or `[%eval #-3]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Evaluation {
+    Centipawns(i32),
+    /// Mate in the given number of moves, negative when Black is mating.
+    MateIn(i32),
+}
+impl std::str::FromStr for Evaluation {
+    type Err = crate::errors::ChessError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(mate) = s.strip_prefix('#') {
+            return mate
+                .parse()
+                .map(Self::MateIn)
+                .map_err(|_| invalid_command(s));
+        }
+        let pawns: f64 = s.parse().map_err(|_| invalid_command(s))?;
+        if !pawns.is_finite() || pawns.abs() > 1_000_000.0 {
+            return Err(invalid_command(s));
+        }
+        #[allow(clippy::cast_possible_truncation)]
+        Ok(Self::Centipawns((pawns * 100.0).round() as i32))
+    }
+}
+impl std::fmt::Display for Evaluation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MateIn(moves) => write!(f, "#{moves}"),
+            Self::Centipawns(centipawns) => {
+                let sign = if *centipawns < 0 { "-" } else { "" };
+                let centipawns = centipawns.unsigned_abs();
+                write!(f, "{sign}{}.{:02}", centipawns / 100, centipawns % 100)
+            }
+        }
+    }
+}
+
+/// Typed values of the embedded commands found in a move comment.
+///
+/// Lichess and Chess.com exports carry clock times, engine evaluations and board
+/// drawings as `[%command arguments]` sequences inside comments. They are parsed
+/// out of the comment text so they can be used directly, and written back on export.
+#[derive(Debug, Clone, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct MoveAnnotations {
+    /// Time left on the player's clock after the move, from `%clk`.
+    #[serde(with = "crate::serialization::millis")]
+    pub clock: Option<std::time::Duration>,
+    /// Time spent on the move, from `%emt`.
+    #[serde(with = "crate::serialization::millis")]
+    pub elapsed: Option<std::time::Duration>,
+    /// Engine evaluation after the move, from `%eval`.
+    pub eval: Option<Evaluation>,
+    /// Search depth of the evaluation, when given as `[%eval 0.17,20]`.
+    pub eval_depth: Option<u32>,
+    /// Highlighted squares, from `%csl`.
+    pub squares: Vec<SquareHighlight>,
+    /// Arrows drawn on the board, from `%cal`.
+    pub arrows: Vec<Arrow>,
+}
+impl MoveAnnotations {
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+    /// Parses the known commands out of a comment, returning the remaining text.
+    ///
+    /// Unknown or malformed commands are left in the text untouched.
+    pub fn extract(&mut self, comment: &str) -> String {
+        if !comment.contains("[%") {
+            return comment.trim().to_string();
+        }
+        let mut text = String::new();
+        let mut rest = comment;
+        while let Some(start) = rest.find("[%") {
+            let Some(len) = rest[start..].find(']') else {
+                break;
+            };
+            let command = &rest[start + 2..start + len];
+            text.push_str(&rest[..start]);
+            if self.apply(command).is_err() {
+                text.push_str(&rest[start..=start + len]);
+            }
+            rest = &rest[start + len + 1..];
+        }
+        text.push_str(rest);
+        text.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+    fn apply(&mut self, command: &str) -> Result<(), crate::errors::ChessError> {
+        let (name, args) = command
+            .split_once(char::is_whitespace)
+            .unwrap_or((command, ""));
+        let args = args.trim();
+        match name {
+            "clk" => self.clock = Some(parse_clock(args)?),
+            "emt" => self.elapsed = Some(parse_clock(args)?),
+            "eval" => {
+                let (eval, depth) = args.split_once(',').unwrap_or((args, ""));
+                let depth = match depth.trim() {
+                    "" => None,
+                    depth => Some(depth.parse().map_err(|_| invalid_command(command))?),
+                };
+                self.eval = Some(eval.trim().parse()?);
+                self.eval_depth = depth;
+            }
+            "csl" => self.squares.extend(parse_list::<SquareHighlight>(args)?),
+            "cal" => self.arrows.extend(parse_list::<Arrow>(args)?),
+            _ => return Err(invalid_command(command)),
+        }
+        Ok(())
+    }
+}
+impl std::fmt::Display for MoveAnnotations {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut commands = Vec::new();
+        if let Some(eval) = self.eval {
+            match self.eval_depth {
+                Some(depth) => commands.push(format!("[%eval {eval},{depth}]")),
+                None => commands.push(format!("[%eval {eval}]")),
+            }
+        }
+        if let Some(clock) = self.clock {
+            commands.push(format!("[%clk {}]", format_clock(clock)));
+        }
+        if let Some(elapsed) = self.elapsed {
+            commands.push(format!("[%emt {}]", format_clock(elapsed)));
+        }
+        if !self.squares.is_empty() {
+            commands.push(format!("[%csl {}]", join_list(&self.squares)));
+        }
+        if !self.arrows.is_empty() {
+            commands.push(format!("[%cal {}]", join_list(&self.arrows)));
+        }
+        write!(f, "{}", commands.join(" "))
+    }
+}
+
+fn invalid_command(value: &str) -> crate::errors::ChessError {
+    crate::errors::ChessError::InvalidValue {
+        kind: "comment command",
+        value: value.to_string(),
+    }
+}
+
+fn parse_square(s: &str) -> Result<shakmaty::Square, crate::errors::ChessError> {
+    shakmaty::Square::from_ascii(s.as_bytes()).map_err(|_| invalid_command(s))
+}
+
+fn parse_list<T: std::str::FromStr<Err = crate::errors::ChessError>>(
+    args: &str,
+) -> Result<Vec<T>, crate::errors::ChessError> {
+    args.split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::parse)
+        .collect()
+}
+
+fn join_list<T: std::fmt::Display>(items: &[T]) -> String {
+    items
+        .iter()
+        .map(std::string::ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Parses `H:MM:SS` clock values, with optional fractions of a second down to nanoseconds.
+fn parse_clock(s: &str) -> Result<std::time::Duration, crate::errors::ChessError> {
+    let number = |digits: &str| {
+        if digits.is_empty() || !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+            return Err(invalid_command(s));
+        }
+        digits.parse::<u64>().map_err(|_| invalid_command(s))
+    };
+    let mut parts = s.split(':').rev();
+    let seconds = parts.next().unwrap_or_default();
+    let (seconds, fraction) = match seconds.split_once('.') {
+        Some((seconds, fraction)) => (seconds, Some(fraction)),
+        None => (seconds, None),
+    };
+    let seconds = number(seconds)?;
+    let minutes = parts.next().map(number).transpose()?;
+    let hours = parts.next().map(number).transpose()?;
+    // Minutes and seconds past a larger unit stay below 60, so clocks export as written.
+    if parts.next().is_some()
+        || (minutes.is_some() && seconds >= 60)
+        || (hours.is_some() && minutes.is_some_and(|minutes| minutes >= 60))
+    {
+        return Err(invalid_command(s));
+    }
+    let (minutes, hours) = (minutes.unwrap_or_default(), hours.unwrap_or_default());
+    let nanos = match fraction {
+        Some(fraction) if fraction.len() <= 9 => {
+            number(fraction)? * 10_u64.pow(9 - u32::try_from(fraction.len()).unwrap_or(9))
+        }
+        Some(_) => return Err(invalid_command(s)),
+        None => 0,
+    };
+    // Hours come straight from comments, any value can be written there.
+    let whole = hours
+        .checked_mul(3600)
+        .zip(minutes.checked_mul(60))
+        .and_then(|(hours, minutes)| hours.checked_add(minutes)?.checked_add(seconds))
+        .ok_or_else(|| invalid_command(s))?;
+    std::time::Duration::from_secs(whole)
+        .checked_add(std::time::Duration::from_nanos(nanos))
+        .ok_or_else(|| invalid_command(s))
+}
+
+/// Writes a clock value as `H:MM:SS`, with the fraction of a second as precise as it was read.
+fn format_clock(clock: std::time::Duration) -> String {
+    let seconds = clock.as_secs();
+    let formatted = format!(
+        "{}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    );
+    match clock.subsec_nanos() {
+        0 => formatted,
+        nanos => format!(
+            "{formatted}.{}",
+            format!("{nanos:09}").trim_end_matches('0')
+        ),
+    }
+}
+
+crate::serialization::serde_as_string!(Evaluation, SquareHighlight, Arrow);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_lichess_comment() {
+        let mut annotations = MoveAnnotations::default();
+        let text = annotations.extract(" Inaccuracy. [%eval -0.35,18] [%clk 0:02:59.5] ");
+        assert_eq!(text, "Inaccuracy.");
+        assert_eq!(annotations.eval, Some(Evaluation::Centipawns(-35)));
+        assert_eq!(annotations.eval_depth, Some(18));
+        assert_eq!(
+            annotations.clock,
+            Some(std::time::Duration::from_millis(179_500))
+        );
+        assert_eq!(annotations.to_string(), "[%eval -0.35,18] [%clk 0:02:59.5]");
+    }
+
+    #[test]
+    fn test_extract_drawings_and_mate() {
+        let mut annotations = MoveAnnotations::default();
+        let text =
+            annotations.extract("[%csl Gd4,Rf7][%cal Ge2e4,Bb1c3] [%eval #-3] [%emt 0:00:12]");
+        assert!(text.is_empty());
+        assert_eq!(annotations.eval, Some(Evaluation::MateIn(-3)));
+        assert_eq!(
+            annotations.elapsed,
+            Some(std::time::Duration::from_secs(12))
+        );
+        assert_eq!(
+            annotations.squares,
+            vec![
+                SquareHighlight {
+                    color: AnnotationColor::Green,
+                    square: shakmaty::Square::D4
+                },
+                SquareHighlight {
+                    color: AnnotationColor::Red,
+                    square: shakmaty::Square::F7
+                },
+            ]
+        );
+        assert_eq!(
+            annotations.arrows[1],
+            Arrow {
+                color: AnnotationColor::Blue,
+                from: shakmaty::Square::B1,
+                to: shakmaty::Square::C3
+            }
+        );
+        assert_eq!(
+            annotations.to_string(),
+            "[%eval #-3] [%emt 0:00:12] [%csl Gd4,Rf7] [%cal Ge2e4,Bb1c3]"
+        );
+    }
+
+    #[test]
+    fn test_keep_unknown_commands() {
+        let mut annotations = MoveAnnotations::default();
+        let text = annotations.extract("[%timestamp 12] [%clk bogus] [%eval 1.5] ok");
+        assert_eq!(text, "[%timestamp 12] [%clk bogus] ok");
+        assert_eq!(annotations.eval, Some(Evaluation::Centipawns(150)));
+        assert_eq!(annotations.clock, None);
+    }
+
+    #[test]
+    fn test_clock_limits() {
+        let mut annotations = MoveAnnotations::default();
+        for clock in [
+            "99999999999999999:00:00",
+            "5124095576030431:59:59",
+            "1:00:18000000000000000000",
+            "0:+1:00",
+        ] {
+            let command = format!("[%clk {clock}]");
+            assert_eq!(annotations.extract(&command), command);
+        }
+        assert_eq!(annotations.clock, None);
+
+        for clock in [
+            "0:00:09.05",
+            "1:02:03.125",
+            "0:02:59.5",
+            "0:00:00.000000001",
+            "5124095576030431:00:00",
+        ] {
+            let text = annotations.extract(&format!("[%clk {clock}]"));
+            assert!(text.is_empty());
+            assert_eq!(annotations.to_string(), format!("[%clk {clock}]"));
+        }
+        let game = "1. e4 { [%clk 99999999999999999:00:00] } *"
+            .parse::<crate::RookyGame>()
+            .unwrap();
+        assert_eq!(game.moves[0].annotations.clock, None);
+        assert_eq!(
+            game.moves[0].comments_after,
+            vec!["[%clk 99999999999999999:00:00]"]
+        );
+    }
+}
@@ -0,0 +1,546 @@
+//! Reading PGN files that hold many games, like TWIC issues or club databases.
+//!
+//! [`PgnSplitter`] cuts a stream of bytes into the text of each game as the bytes arrive.
+//! [`PgnDatabase`] parses every game found in a reader or a sequence of chunks, and
+//! [`PgnStream`] does the same for an asynchronous stream of chunks, like a download.
+
+/// Size of the chunks read from a [`std::io::Read`] source.
+const READ_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Splits a PGN stream into games, one line at a time.
+///
+/// A new game starts when a tag pair line follows movetext, or when movetext follows a
+/// game termination marker (`1-0`, `0-1`, `1/2-1/2` or `*`). Lines inside brace comments
+/// are never taken as tags, and both LF and CRLF line endings are accepted.
+#[derive(Debug, Default, Clone)]
+pub struct PgnSplitter {
+    buffer: Vec<u8>,
+    /// Offset of the game being read.
+    start: usize,
+    /// Offset of the first line not yet scanned.
+    scanned: usize,
+    /// Lines before the game being read, and before the last game returned.
+    lines: usize,
+    game_lines: usize,
+    in_movetext: bool,
+    in_comment: bool,
+    terminated: bool,
+}
+impl PgnSplitter {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Adds the next bytes of the stream.
+    pub fn push(&mut self, chunk: &[u8]) {
+        // Drop the games already returned once they take most of the buffer,
+        // so every byte is only moved a bounded number of times.
+        if self.start > 0 && self.start * 2 >= self.buffer.len() {
+            self.buffer.drain(..self.start);
+            self.scanned -= self.start;
+            self.start = 0;
+        }
+        self.buffer.extend_from_slice(chunk);
+        if self.scanned == 0 && self.buffer.starts_with(b"\xEF\xBB\xBF") {
+            self.start = 3;
+            self.scanned = 3;
+        }
+    }
+    /// Returns the next game that is known to be complete.
+    pub fn next_game(&mut self) -> Option<Vec<u8>> {
+        while let Some(length) = self.buffer[self.scanned..]
+            .iter()
+            .position(|byte| *byte == b'\n')
+        {
+            let line_start = self.scanned;
+            self.scanned += length + 1;
+            let game = if self.starts_game(line_start) {
+                self.take_game(line_start)
+            } else {
+                None
+            };
+            self.read_line(line_start);
+            if game.is_some() {
+                return game;
+            }
+        }
+        None
+    }
+    /// Returns the games left once the stream has ended, call it until it returns `None`.
+    pub fn finish(&mut self) -> Option<Vec<u8>> {
+        // The last line may start a new game even without a line ending.
+        if self.buffer.last().is_some_and(|byte| *byte != b'\n') {
+            self.buffer.push(b'\n');
+        }
+        if let Some(game) = self.next_game() {
+            return Some(game);
+        }
+        self.scanned = self.buffer.len();
+        let game = self.take_game(self.buffer.len());
+        self.buffer.clear();
+        self.start = 0;
+        self.scanned = 0;
+        game
+    }
+    fn starts_game(&self, line_start: usize) -> bool {
+        let line = self.buffer[line_start..self.scanned].trim_ascii();
+        if self.in_comment || line.is_empty() || line.starts_with(b"%") {
+            return false;
+        }
+        (self.in_movetext && line.starts_with(b"[")) || (self.terminated && !line.starts_with(b"["))
+    }
+    /// Line of the stream where the last returned game starts, counting from 1.
+    #[must_use]
+    pub const fn game_line(&self) -> usize {
+        self.game_lines + 1
+    }
+    /// Cuts the game read so far, skipping it if it only holds blank lines.
+    fn take_game(&mut self, end: usize) -> Option<Vec<u8>> {
+        let game = &self.buffer[self.start..end];
+        self.game_lines = self.lines;
+        #[allow(clippy::naive_bytecount)]
+        let lines = game.iter().filter(|byte| **byte == b'\n').count();
+        self.lines += lines;
+        let game = (!game.trim_ascii().is_empty()).then(|| game.to_vec());
+        self.start = end;
+        self.in_movetext = false;
+        self.terminated = false;
+        game
+    }
+    /// Tracks comments and game termination markers found on a line.
+    fn read_line(&mut self, line_start: usize) {
+        let trimmed = self.buffer[line_start..self.scanned].trim_ascii();
+        if trimmed.is_empty() || trimmed.starts_with(b"%") {
+            return;
+        }
+        if !self.in_comment && trimmed.starts_with(b"[") {
+            return;
+        }
+        self.in_movetext = true;
+        let mut in_comment = self.in_comment;
+        let mut last_token: &[u8] = &[];
+        let mut token_start = None;
+        for (index, byte) in trimmed.iter().enumerate() {
+            let separator = in_comment || byte.is_ascii_whitespace() || b"{};()".contains(byte);
+            if separator {
+                if let Some(start) = token_start.take() {
+                    last_token = &trimmed[start..index];
+                }
+            } else if token_start.is_none() {
+                token_start = Some(index);
+            }
+            match byte {
+                b'{' if !in_comment => in_comment = true,
+                b'}' if in_comment => in_comment = false,
+                b';' if !in_comment => break,
+                _ => {}
+            }
+        }
+        if let Some(start) = token_start {
+            last_token = &trimmed[start..];
+        }
+        self.terminated =
+            !in_comment && [&b"1-0"[..], b"0-1", b"1/2-1/2", b"*"].contains(&last_token);
+        self.in_comment = in_comment;
+    }
+}
+
+/// Games of a PGN database, read one at a time.
+///
+/// Every item is the result of parsing one game. Malformed games are reported as
+/// [`crate::errors::ChessError::Game`] with their index, and reading goes on with the
+/// next one.
+pub struct PgnDatabase<I> {
+    chunks: I,
+    games: DatabaseGames,
+}
+impl<R: std::io::Read> PgnDatabase<ReadChunks<R>> {
+    pub fn new(reader: R) -> Self {
+        Self::from_results(ReadChunks(reader))
+    }
+}
+impl<I, C> PgnDatabase<I>
+where
+    I: Iterator<Item = std::io::Result<C>>,
+    C: AsRef<[u8]>,
+{
+    /// Reads games from chunks of bytes that may fail to arrive.
+    ///
+    /// A failed chunk is reported as an error of the game being read and ends the database.
+    pub fn from_results(chunks: I) -> Self {
+        Self {
+            chunks,
+            games: DatabaseGames::default(),
+        }
+    }
+}
+impl<I, C> PgnDatabase<std::iter::Map<I, fn(C) -> std::io::Result<C>>>
+where
+    I: Iterator<Item = C>,
+    C: AsRef<[u8]>,
+{
+    /// Reads games from chunks of bytes, split anywhere.
+    pub fn from_chunks(chunks: impl IntoIterator<IntoIter = I>) -> Self {
+        Self::from_results(chunks.into_iter().map(Ok as fn(C) -> std::io::Result<C>))
+    }
+}
+impl<I, C> Iterator for PgnDatabase<I>
+where
+    I: Iterator<Item = std::io::Result<C>>,
+    C: AsRef<[u8]>,
+{
+    type Item = Result<crate::RookyGame, crate::errors::ChessError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(game) = self.games.next_game() {
+                return Some(game);
+            }
+            if self.games.done {
+                return None;
+            }
+            if let Some(error) = self.games.read(self.chunks.next()) {
+                return Some(Err(error));
+            }
+        }
+    }
+}
+
+/// Games of a PGN database arriving as a stream of byte chunks.
+///
+/// Works like [`PgnDatabase`]: each item is the result of parsing one game, and errors
+/// carry the index of the game that failed.
+pub struct PgnStream<S> {
+    chunks: S,
+    games: DatabaseGames,
+}
+impl<S> PgnStream<OkChunks<S>> {
+    /// Reads games from a stream of chunks of bytes, split anywhere.
+    pub fn new(chunks: S) -> Self {
+        Self::from_results(OkChunks(chunks))
+    }
+}
+impl<S> PgnStream<S> {
+    /// Reads games from a stream of chunks that may fail to arrive.
+    ///
+    /// A failed chunk is reported as an error of the game being read and ends the stream.
+    pub fn from_results(chunks: S) -> Self {
+        Self {
+            chunks,
+            games: DatabaseGames::default(),
+        }
+    }
+}
+impl<S, C> futures_core::Stream for PgnStream<S>
+where
+    S: futures_core::Stream<Item = std::io::Result<C>> + Unpin,
+    C: AsRef<[u8]>,
+{
+    type Item = Result<crate::RookyGame, crate::errors::ChessError>;
+
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(game) = this.games.next_game() {
+                return std::task::Poll::Ready(Some(game));
+            }
+            if this.games.done {
+                return std::task::Poll::Ready(None);
+            }
+            let chunk = std::task::ready!(std::pin::Pin::new(&mut this.chunks).poll_next(cx));
+            if let Some(error) = this.games.read(chunk) {
+                return std::task::Poll::Ready(Some(Err(error)));
+            }
+        }
+    }
+}
+
+/// Games split from the chunks of a database, shared by [`PgnDatabase`] and [`PgnStream`].
+#[derive(Debug, Default)]
+struct DatabaseGames {
+    splitter: PgnSplitter,
+    /// Index of the next game.
+    index: usize,
+    /// Whether the last chunk was read.
+    done: bool,
+}
+impl DatabaseGames {
+    /// Parses the next complete game, or the games left once every chunk was read.
+    fn next_game(&mut self) -> Option<Result<crate::RookyGame, crate::errors::ChessError>> {
+        let game = if self.done {
+            self.splitter.finish()
+        } else {
+            self.splitter.next_game()
+        }?;
+        self.index += 1;
+        Some(parse_game(self.index - 1, self.splitter.game_line(), &game))
+    }
+    /// Reads the next chunk, `None` once there are no more.
+    ///
+    /// A failed chunk ends the database, and is returned as an error of the game being read.
+    fn read<C: AsRef<[u8]>>(
+        &mut self,
+        chunk: Option<std::io::Result<C>>,
+    ) -> Option<crate::errors::ChessError> {
+        match chunk {
+            Some(Ok(chunk)) => self.splitter.push(chunk.as_ref()),
+            Some(Err(error)) => {
+                self.done = true;
+                let line = self.splitter.lines + 1;
+                self.splitter = PgnSplitter::new();
+                self.index += 1;
+                return Some(crate::errors::ChessError::Game {
+                    index: self.index - 1,
+                    line,
+                    error: Box::new(error.into()),
+                });
+            }
+            None => self.done = true,
+        }
+        None
+    }
+}
+
+/// Chunks of a stream that can not fail, see [`PgnStream::new`].
+pub struct OkChunks<S>(S);
+impl<S> futures_core::Stream for OkChunks<S>
+where
+    S: futures_core::Stream + Unpin,
+{
+    type Item = std::io::Result<S::Item>;
+
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        std::pin::Pin::new(&mut self.get_mut().0)
+            .poll_next(cx)
+            .map(|chunk| chunk.map(Ok))
+    }
+}
+
+/// Parses a game found at `line` of a database, locating its errors in the whole database.
+fn parse_game(
+    index: usize,
+    line: usize,
+    game: &[u8],
+) -> Result<crate::RookyGame, crate::errors::ChessError> {
+    crate::RookyGame::try_from(game).map_err(|error| {
+        let error = match error {
+            crate::errors::ChessError::At {
+                line: game_line,
+                column,
+                error,
+            } => crate::errors::ChessError::At {
+                line: line + game_line - 1,
+                column,
+                error,
+            },
+            error => error,
+        };
+        crate::errors::ChessError::Game {
+            index,
+            line,
+            error: Box::new(error),
+        }
+    })
+}
+
+/// Chunks of bytes read from a [`std::io::Read`] source.
+pub struct ReadChunks<R>(R);
+impl<R: std::io::Read> Iterator for ReadChunks<R> {
+    type Item = std::io::Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut chunk = vec![0; READ_CHUNK_SIZE];
+        loop {
+            return match self.0.read(&mut chunk) {
+                Ok(0) => None,
+                Ok(read) => {
+                    chunk.truncate(read);
+                    Some(Ok(chunk))
+                }
+                Err(error) if error.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(error) => Some(Err(error)),
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DATABASE: &str = r#"[Event "Club Championship"]
+[Site "?"]
+[Date "2024.05.01"]
+[Round "1"]
+[White "alice"]
+[Black "bob"]
+[Result "1-0"]
+
+1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6 4. Qxf7# 1-0
+
+[Event "Club Championship"]
+[Site "?"]
+[Date "2024.05.01"]
+[Round "1"]
+[White "carol"]
+[Black "dave"]
+[Result "0-1"]
+
+0-1
+
+[Event "Club Championship"]
+[Site "?"]
+[Date "2024.05.01"]
+[Round "2"]
+[White "bob"]
+[Black "carol"]
+[Result "*"]
+
+1. d4 { A long comment
+[that looks like a tag]
+on several lines } d5 *
+[Event "Club Championship"]
+[Result "1/2-1/2"]
+
+1. c4 c5 1/2-1/2
+"#;
+
+    #[test]
+    fn test_split_games() {
+        let mut splitter = PgnSplitter::new();
+        splitter.push(DATABASE.as_bytes());
+        let mut games = Vec::new();
+        while let Some(game) = splitter.next_game() {
+            games.push(game);
+        }
+        assert_eq!(games.len(), 3);
+        games.extend(splitter.finish());
+        assert_eq!(games.len(), 4);
+        assert_eq!(games.concat(), DATABASE.as_bytes());
+        assert!(splitter.finish().is_none());
+    }
+
+    #[test]
+    fn test_read_database() {
+        let games = PgnDatabase::new(DATABASE.as_bytes()).collect::<Vec<_>>();
+        assert_eq!(games.len(), 4);
+        assert!(games[0].is_ok());
+        // A forfeit, decided without any move
+        let forfeit = games[1].as_ref().unwrap();
+        assert!(forfeit.moves.is_empty());
+        assert_eq!(
+            (forfeit.white.as_str(), forfeit.black.as_str()),
+            ("carol", "dave")
+        );
+        assert!(forfeit.outcome.is_finished());
+        let third = games[2].as_ref().unwrap();
+        assert_eq!(third.moves.len(), 2);
+        assert_eq!(
+            third.moves[0].comments_after,
+            vec!["A long comment [that looks like a tag] on several lines".to_string()]
+        );
+        assert_eq!(games[3].as_ref().unwrap().moves.len(), 2);
+    }
+
+    #[test]
+    fn test_read_database_in_chunks() {
+        let crlf = format!("\u{feff}{}", DATABASE.replace('\n', "\r\n"));
+        let expected = PgnDatabase::new(DATABASE.as_bytes())
+            .map(|game| game.ok().map(|game| game.moves))
+            .collect::<Vec<_>>();
+        for size in [1, 7, 64] {
+            let games = PgnDatabase::from_chunks(crlf.as_bytes().chunks(size))
+                .map(|game| game.ok().map(|game| game.moves))
+                .collect::<Vec<_>>();
+            assert_eq!(games, expected);
+        }
+
+        let without_headers = "1. e4 e5 1-0\n1. d4 d5 0-1\n\n1. c4 *";
+        assert_eq!(PgnDatabase::new(without_headers.as_bytes()).count(), 3);
+    }
+
+    #[test]
+    fn test_reject_trailing_text() {
+        let database = format!("{DATABASE}\nExported by some tool\n");
+        let error = PgnDatabase::new(database.as_bytes())
+            .last()
+            .unwrap()
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            crate::errors::ChessError::Game { index: 4, ref error, .. } if error.code() == "empty_game"
+        ));
+    }
+
+    #[test]
+    fn test_stream_database() {
+        use futures_core::Stream;
+        let chunks = DATABASE.as_bytes().chunks(5).collect::<Vec<_>>();
+        let mut stream = PgnStream::new(futures_util::stream::iter(chunks));
+        let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+        let mut games = Vec::new();
+        while let std::task::Poll::Ready(Some(game)) =
+            std::pin::Pin::new(&mut stream).poll_next(&mut cx)
+        {
+            games.push(game.ok().map(|game| game.moves));
+        }
+        let expected = PgnDatabase::new(DATABASE.as_bytes())
+            .map(|game| game.ok().map(|game| game.moves))
+            .collect::<Vec<_>>();
+        assert_eq!(games, expected);
+
+        let chunks = [
+            Ok(DATABASE.as_bytes()),
+            Err(std::io::Error::from(std::io::ErrorKind::ConnectionReset)),
+            Ok(DATABASE.as_bytes()),
+        ];
+        let mut stream = PgnStream::from_results(futures_util::stream::iter(chunks));
+        let mut games = Vec::new();
+        while let std::task::Poll::Ready(Some(game)) =
+            std::pin::Pin::new(&mut stream).poll_next(&mut cx)
+        {
+            games.push(game);
+        }
+        assert_eq!(games.len(), 4);
+        let Err(crate::errors::ChessError::Game { index, line, error }) = &games[3] else {
+            panic!("Failed chunk not reported: {:?}", games[3]);
+        };
+        assert_eq!((*index, *line), (3, DATABASE.lines().count() - 3));
+        assert_eq!(error.code(), "invalid_pgn");
+
+        let chunks = [
+            Ok(DATABASE.as_bytes()),
+            Err(std::io::Error::from(std::io::ErrorKind::ConnectionReset)),
+            Ok(DATABASE.as_bytes()),
+        ];
+        let read = PgnDatabase::from_results(chunks.into_iter()).collect::<Vec<_>>();
+        assert_eq!(read.len(), games.len());
+        assert_eq!(
+            read[3].as_ref().unwrap_err().location(),
+            games[3].as_ref().unwrap_err().location()
+        );
+    }
+
+    #[test]
+    fn test_locate_database_errors() {
+        let database =
+            format!("{DATABASE}\n[Event \"?\"]\n[FEN \"8/8/8/8/8/8/8/8 w - - 0 1\"]\n\n1. e4 *\n");
+        let event_line = DATABASE.lines().count() + 2;
+        let error = PgnDatabase::new(database.as_bytes())
+            .last()
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(error.code(), "invalid_fen");
+        assert_eq!(error.location(), Some((event_line + 1, 1)));
+        assert!(matches!(
+            error,
+            crate::errors::ChessError::Game { index: 4, line, .. } if line == event_line
+        ));
+    }
+}
@@ -1,25 +1,88 @@
+/// Errors of the Rooky protocol.
+///
+/// Every error has a stable [`ChessError::code`], so applications can show their own
+/// localized message instead of the English [`std::fmt::Display`] one.
 #[derive(Debug)]
 pub enum ChessError {
+    /// A move or variation looked up in a game does not exist.
     NotFound(&'static str),
+    /// The PGN could not be read at all.
     InvalidPgn(std::io::Error),
+    /// Move that can not be played, `ply` counts the half moves from the starting position.
+    IllegalMove { ply: usize, san: String },
+    /// A tag pair holds a value its tag does not allow.
+    InvalidTag { tag: String, value: String },
+    /// A date that does not follow the `YYYY.MM.DD` format.
+    InvalidDate(String),
+    /// A FEN that can not be parsed, or does not describe a legal position.
+    InvalidFen { fen: String, reason: String },
+    /// The `Variant` tag names rules that are not supported.
+    UnsupportedVariant(String),
+    /// The PGN holds no game: no moves, and neither tag pairs nor a termination marker.
+    EmptyGame,
+    /// A value that is not valid for its kind, like a country code or a comment command.
+    InvalidValue { kind: &'static str, value: String },
+    /// The recorded result is not the one the final position of the game ends with.
+    ResultMismatch { recorded: String, detected: String },
+    /// A signer failed, or refused, to sign, encrypt or decrypt a note.
+    Signer(String),
+    /// Error found at a line and column of the PGN text, both counting from 1.
+    At {
+        line: usize,
+        column: usize,
+        error: Box<Self>,
+    },
+    /// Error found in one of the games of a PGN database, counting from 0.
+    /// `line` is the line where the game starts.
+    Game {
+        index: usize,
+        line: usize,
+        error: Box<Self>,
+    },
 }
-impl std::error::Error for ChessError {
-    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+impl ChessError {
+    /// Stable identifier of the kind of error, located errors report the code of their cause.
+    #[must_use]
+    pub fn code(&self) -> &'static str {
         match self {
-            Self::InvalidPgn(err) => Some(err),
-            Self::NotFound(_) => None,
+            Self::NotFound(_) => "not_found",
+            Self::InvalidPgn(_) => "invalid_pgn",
+            Self::IllegalMove { .. } => "illegal_move",
+            Self::InvalidTag { .. } => "invalid_tag",
+            Self::InvalidDate(_) => "invalid_date",
+            Self::InvalidFen { .. } => "invalid_fen",
+            Self::UnsupportedVariant(_) => "unsupported_variant",
+            Self::EmptyGame => "empty_game",
+            Self::InvalidValue { .. } => "invalid_value",
+            Self::ResultMismatch { .. } => "result_mismatch",
+            Self::Signer(_) => "signer",
+            Self::At { error, .. } | Self::Game { error, .. } => error.code(),
+        }
+    }
+    /// Line and column where the error was found, if known.
+    #[must_use]
+    pub fn location(&self) -> Option<(usize, usize)> {
+        match self {
+            Self::At { line, column, .. } => Some((*line, *column)),
+            Self::Game { line, error, .. } => error.location().or(Some((*line, 1))),
+            _ => None,
         }
     }
-    fn description(&self) -> &str {
+    /// The error without its location.
+    #[must_use]
+    pub fn cause(&self) -> &Self {
         match self {
-            Self::NotFound(msg) => msg,
-            Self::InvalidPgn(e) => Box::leak(format!("Invalid PGN: {e}").into_boxed_str()),
+            Self::At { error, .. } | Self::Game { error, .. } => error.cause(),
+            error => error,
         }
     }
-    fn cause(&self) -> Option<&dyn std::error::Error> {
+}
+impl std::error::Error for ChessError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
-            Self::InvalidPgn(ref err) => Some(err),
-            Self::NotFound(_) => None,
+            Self::InvalidPgn(err) => Some(err),
+            Self::At { error, .. } | Self::Game { error, .. } => Some(error.as_ref()),
+            _ => None,
         }
     }
 }
@@ -33,6 +96,26 @@ impl std::fmt::Display for ChessError {
         match self {
             Self::NotFound(msg) => write!(f, "Not found: {msg}"),
             Self::InvalidPgn(err) => write!(f, "Invalid PGN: {err}"),
+            Self::IllegalMove { ply, san } => write!(f, "Illegal move at ply {ply}: {san}"),
+            Self::InvalidTag { tag, value } => write!(f, "Invalid {tag} tag: \"{value}\""),
+            Self::InvalidDate(date) => write!(f, "Invalid date: {date}"),
+            Self::InvalidFen { fen, reason } => write!(f, "Invalid FEN \"{fen}\": {reason}"),
+            Self::UnsupportedVariant(variant) => write!(f, "Unsupported variant: {variant}"),
+            Self::EmptyGame => write!(f, "No moves found"),
+            Self::InvalidValue { kind, value } => write!(f, "Invalid {kind}: {value}"),
+            Self::ResultMismatch { recorded, detected } => {
+                write!(
+                    f,
+                    "Result {recorded} does not match the final position, {detected}"
+                )
+            }
+            Self::Signer(reason) => write!(f, "Signer error: {reason}"),
+            Self::At {
+                line,
+                column,
+                error,
+            } => write!(f, "{error} (line {line}, column {column})"),
+            Self::Game { index, error, .. } => write!(f, "Game {index}: {error}"),
         }
     }
 }
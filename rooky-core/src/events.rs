@@ -0,0 +1,367 @@
+//! Nostr events that carry chess games.
+//!
+//! Games are published as NIP-64 events: kind 64 with the PGN of the game as content.
+//! Earlier versions published them as kind 1 text notes, which are still read but no
+//! longer written, so games stay out of text note feeds.
+//!
+//! Game events carry a NIP-31 `alt` tag describing them for clients that do not know
+//! the kind, and tags that relays index so games can be searched:
+//!
+//! - A `p` tag for each player with a known pubkey, marked `white` or `black`.
+//! - NIP-32 labels: an `L` tag with each namespace used, and `l` tags with the values.
+//!
+//! | Namespace       | Value                                            |
+//! |-----------------|--------------------------------------------------|
+//! | `rooky.player`  | Name of each player                              |
+//! | `rooky.event`   | Name of the event                                |
+//! | `rooky.date`    | Date of the game in ISO 8601, like `2025-01-04`  |
+//! | `rooky.result`  | Result as in PGN, like `1-0` or `*`              |
+//! | `rooky.eco`     | ECO code of the opening, like `C65`              |
+//! | `rooky.opening` | Opening without its variation, like `Ruy Lopez`  |
+//!
+//! The `created_at` of a game event is the date of the game, when its year is known.
+//! Relays only take timestamps from 1970 on, so games played earlier are dated at 0 and
+//! only their `rooky.date` label holds the actual date.
+//!
+//! # Annotated games
+//!
+//! Games that are edited after they are published, like annotated games and studies, use
+//! the addressable kind 30064 instead, with the same content and tags and a `d` tag that
+//! stays the same across versions. Relays only keep the latest version of each address,
+//! `30064:<pubkey>:<d>`, older versions stay in the history of the
+//! [`RookyGameEntry`](crate::idb::RookyGameEntry) that received them.
+//!
+//! Since each version is published when it is saved, its `created_at` is the time it was
+//! saved rather than the date of the game.
+
+/// Kind of the NIP-64 events holding a game.
+pub const GAME_KIND: u32 = 64;
+/// Kind of the text notes games were published as before NIP-64.
+pub const LEGACY_GAME_KIND: u32 = 1;
+/// Kind of the addressable events holding a game that can be edited.
+pub const ANNOTATED_GAME_KIND: u32 = 30064;
+
+pub const PLAYER_LABEL: &str = "rooky.player";
+pub const EVENT_LABEL: &str = "rooky.event";
+pub const DATE_LABEL: &str = "rooky.date";
+pub const RESULT_LABEL: &str = "rooky.result";
+pub const ECO_LABEL: &str = "rooky.eco";
+pub const OPENING_LABEL: &str = "rooky.opening";
+
+/// Whether events of this kind hold a game.
+#[must_use]
+pub const fn is_game_kind(kind: u32) -> bool {
+    matches!(kind, GAME_KIND | LEGACY_GAME_KIND | ANNOTATED_GAME_KIND)
+}
+
+/// Event publishing a version of an annotated game, see the [module docs](self).
+///
+/// `identifier` names the game among those of its author, and has to be the same for
+/// every version, like a random id chosen when the game is first saved.
+#[must_use]
+pub fn annotated_game_note(
+    game: &crate::RookyGame,
+    identifier: &str,
+) -> nostr_minions::nostro2::NostrNote {
+    let mut note = nostr_minions::nostro2::NostrNote {
+        kind: ANNOTATED_GAME_KIND,
+        content: game.to_pgn(),
+        ..Default::default()
+    };
+    note.tags.add_parameter_tag(identifier);
+    note.tags.0.extend(game_tags(game));
+    note
+}
+
+/// Address of an addressable event, `<kind>:<pubkey>:<d>`, shared by all its versions.
+#[must_use]
+pub fn note_address(note: &nostr_minions::nostro2::NostrNote) -> Option<String> {
+    if !(30_000..40_000).contains(&note.kind) {
+        return None;
+    }
+    let identifier = note.tags.first_parameter().unwrap_or_default();
+    Some(format!("{}:{}:{identifier}", note.kind, note.pubkey))
+}
+
+/// Whether a note is signed by its author, with an id matching its content.
+///
+/// `NostrNote::verify` only checks that the signature is well formed, and panics on ids
+/// or keys of the wrong length, so notes received from others are checked here instead.
+#[must_use]
+pub fn verify_note(note: &nostr_minions::nostro2::NostrNote) -> bool {
+    let mut rebuilt = nostr_minions::nostro2::NostrNote {
+        pubkey: note.pubkey.clone(),
+        created_at: note.created_at,
+        kind: note.kind,
+        tags: note.tags.clone(),
+        content: note.content.clone(),
+        ..Default::default()
+    };
+    if rebuilt.serialize_id().is_err() || rebuilt.id != note.id {
+        return false;
+    }
+    let (Some(id), Some(sig), Some(pubkey)) = (
+        note.id.as_deref().and_then(hex_bytes),
+        note.sig.as_deref().and_then(hex_bytes),
+        hex_bytes(&note.pubkey),
+    ) else {
+        return false;
+    };
+    let (Ok(signature), Ok(pubkey)) = (
+        secp256k1::schnorr::Signature::from_slice(&sig),
+        secp256k1::XOnlyPublicKey::from_slice(&pubkey),
+    ) else {
+        return false;
+    };
+    secp256k1::Secp256k1::verification_only()
+        .verify_schnorr(&signature, &id, &pubkey)
+        .is_ok()
+}
+
+fn hex_bytes(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+/// Tags of the event publishing a game, as described in the [module docs](self).
+#[must_use]
+pub fn game_tags(game: &crate::RookyGame) -> Vec<Vec<String>> {
+    let mut tags = vec![vec!["alt".to_string(), alt_text(game)]];
+    let relay = game.relays.first().map_or("", crate::rtr::RelayUrl::as_str);
+    for (pubkey, color) in [(&game.white_pubkey, "white"), (&game.black_pubkey, "black")] {
+        if let Some(pubkey) = pubkey {
+            tags.push(vec![
+                "p".to_string(),
+                pubkey.to_string(),
+                relay.to_string(),
+                color.to_string(),
+            ]);
+        }
+    }
+
+    let classified = game.opening();
+    let eco = game
+        .tags
+        .eco()
+        .or_else(|| classified.map(|found| found.opening.code));
+    // The Opening tag of Lichess exports names the variation too
+    let opening = game
+        .tags
+        .opening()
+        .and_then(|opening| opening.split(':').next())
+        .map(str::trim)
+        .or_else(|| classified.map(|found| found.opening.name));
+    let mut labels = vec![
+        (PLAYER_LABEL, game.white.clone()),
+        (PLAYER_LABEL, game.black.clone()),
+        (RESULT_LABEL, game.outcome.to_string()),
+    ];
+    if game.event != crate::pgn_standards::PgnEvent::Unknown {
+        labels.push((EVENT_LABEL, game.event.to_string()));
+    }
+    if let Some(date) = game.date.to_iso() {
+        labels.push((DATE_LABEL, date));
+    }
+    if let Some(eco) = eco {
+        labels.push((ECO_LABEL, eco.to_string()));
+    }
+    if let Some(opening) = opening {
+        labels.push((OPENING_LABEL, opening.to_string()));
+    }
+    labels.retain(|(_, value)| !value.is_empty() && value != "?");
+
+    let mut namespaces = labels
+        .iter()
+        .map(|(namespace, _)| *namespace)
+        .collect::<Vec<_>>();
+    namespaces.dedup();
+    for namespace in namespaces {
+        tags.push(vec!["L".to_string(), namespace.to_string()]);
+    }
+    for (namespace, value) in labels {
+        tags.push(vec!["l".to_string(), value, namespace.to_string()]);
+    }
+    tags
+}
+
+/// Summary of the game for clients that can not show it, like `Chess game: alice vs bob (1-0)`.
+fn alt_text(game: &crate::RookyGame) -> String {
+    let players = format!("Chess game: {} vs {}", game.white, game.black);
+    if game.outcome.is_finished() {
+        format!("{players} ({})", game.outcome)
+    } else {
+        players
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GAME: &str = r#"[Event "Casual Game"]
+[Site "?"]
+[Date "2025.01.04"]
+[Round "-"]
+[White "alice"]
+[Black "bob"]
+[Result "1-0"]
+[WhitePubkey "3bf0c63fcb93463407af97a5e5ee64fa883d107ef9e558472c4eb9aaaefa459d"]
+[Relays "wss://relay.damus.io"]
+
+1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 1-0
+"#;
+
+    #[test]
+    fn test_hex_bytes() {
+        assert_eq!(hex_bytes("0aFf"), Some(vec![0x0a, 0xff]));
+        assert_eq!(hex_bytes("+a"), None);
+        assert_eq!(hex_bytes("abc"), None);
+        assert_eq!(hex_bytes("é0"), None);
+    }
+
+    fn labels(note: &nostr_minions::nostro2::NostrNote, namespace: &str) -> Vec<String> {
+        note.tags
+            .0
+            .iter()
+            .filter(|tag| tag.first().is_some_and(|name| name == "l"))
+            .filter(|tag| tag.get(2).is_some_and(|label| label == namespace))
+            .map(|tag| tag[1].clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_publish_game_event() {
+        let game = GAME.parse::<crate::RookyGame>().unwrap();
+        let note = nostr_minions::nostro2::NostrNote::from(game.clone());
+        assert_eq!(note.kind, GAME_KIND);
+        assert_eq!(note.content, GAME);
+        assert_eq!(
+            note.tags.find_tags("alt"),
+            vec!["Chess game: alice vs bob (1-0)"]
+        );
+        assert_eq!(
+            note.tags.0.iter().find(|tag| tag[0] == "p").unwrap(),
+            &vec![
+                "p",
+                "3bf0c63fcb93463407af97a5e5ee64fa883d107ef9e558472c4eb9aaaefa459d",
+                "wss://relay.damus.io",
+                "white"
+            ]
+        );
+        assert_eq!(labels(&note, PLAYER_LABEL), vec!["alice", "bob"]);
+        assert_eq!(labels(&note, EVENT_LABEL), vec!["Casual Game"]);
+        assert_eq!(labels(&note, DATE_LABEL), vec!["2025-01-04"]);
+        assert_eq!(labels(&note, RESULT_LABEL), vec!["1-0"]);
+        assert_eq!(labels(&note, ECO_LABEL), vec!["C70"]);
+        assert_eq!(labels(&note, OPENING_LABEL), vec!["Ruy Lopez"]);
+        assert_eq!(note.tags.0.iter().filter(|tag| tag[0] == "L").count(), 6);
+
+        assert_eq!(crate::RookyGame::try_from(note.clone()).unwrap(), game);
+        let legacy = nostr_minions::nostro2::NostrNote {
+            kind: LEGACY_GAME_KIND,
+            ..note.clone()
+        };
+        assert_eq!(crate::RookyGame::try_from(legacy.clone()).unwrap(), game);
+        let text_note = nostr_minions::nostro2::NostrNote {
+            content: "Good game!".to_string(),
+            ..legacy
+        };
+        assert!(crate::RookyGame::try_from(text_note).is_err());
+        for content in ["Good game!", "[Result \"*\"]\n\n*"] {
+            let not_a_game = nostr_minions::nostro2::NostrNote {
+                content: content.to_string(),
+                ..note.clone()
+            };
+            let error = crate::RookyGame::try_from(not_a_game).unwrap_err();
+            assert_eq!(error.code(), "empty_game");
+        }
+        let reaction = nostr_minions::nostro2::NostrNote { kind: 7, ..note };
+        let error = crate::RookyGame::try_from(reaction).unwrap_err();
+        assert_eq!(error.code(), "invalid_value");
+    }
+
+    /// Secret key of the author of the annotated game.
+    const AUTHOR: &str = "4cd8b6e9c5b8ac6f4bb3d9b1c4ab9bd33ed6fcc0cc5c7e1dfa2e7e3d5b1c2a11";
+
+    fn version(game: &crate::RookyGame, saved_at: i64) -> nostr_minions::nostro2::NostrNote {
+        let author = AUTHOR
+            .parse::<nostro2_signer::keypair::NostrKeypair>()
+            .unwrap();
+        let mut note = nostr_minions::nostro2::NostrNote {
+            created_at: saved_at,
+            ..annotated_game_note(game, "ruy-lopez-study")
+        };
+        author.sign_note(&mut note).unwrap();
+        note
+    }
+
+    #[test]
+    fn test_annotated_game_versions() {
+        let mut game = GAME.parse::<crate::RookyGame>().unwrap();
+        let first = version(&game, 1_000);
+        assert_eq!(first.kind, ANNOTATED_GAME_KIND);
+        assert_eq!(first.tags.first_parameter().unwrap(), "ruy-lopez-study");
+        assert_eq!(labels(&first, OPENING_LABEL), vec!["Ruy Lopez"]);
+        assert_eq!(crate::RookyGame::try_from(first.clone()).unwrap(), game);
+        let address = format!("30064:{}:ruy-lopez-study", first.pubkey);
+        assert_eq!(note_address(&first).unwrap(), address);
+        assert!(note_address(&nostr_minions::nostro2::NostrNote::from(game.clone())).is_none());
+
+        game.moves[5]
+            .comments_after
+            .push("The Morphy Defense".to_string());
+        let second = version(&game, 2_000);
+        game.moves[5].comments_after[0] = "The Morphy Defence".to_string();
+        let third = version(&game, 3_000);
+
+        let mut entry =
+            crate::idb::RookyGameEntry::new(second.clone(), crate::idb::GameOrigin::Annotated);
+        assert_eq!(entry.id, address);
+        assert!(entry.add_version(third.clone()));
+        assert!(entry.add_version(first.clone()));
+        assert!(!entry.add_version(first.clone()));
+        assert_eq!(entry.note, third);
+        assert_eq!(entry.history, vec![first.clone(), second.clone()]);
+
+        let mut other_study = version(&game, 4_000);
+        other_study.tags.0[0][1] = "another-study".to_string();
+        assert!(!entry.add_version(other_study));
+        assert!(!entry.add_version(nostr_minions::nostro2::NostrNote::from(game.clone())));
+
+        let mut other_author = nostr_minions::nostro2::NostrNote {
+            created_at: 4_000,
+            ..annotated_game_note(&game, "ruy-lopez-study")
+        };
+        <nostro2_signer::keypair::NostrKeypair as nostr_minions::nostro2::NostrSigner>::generate(
+            false,
+        )
+        .sign_note(&mut other_author)
+        .unwrap();
+        assert!(!entry.add_version(other_author));
+        let mut altered = version(&game, 4_000);
+        altered.content = altered.content.replace("Morphy", "Steinitz");
+        assert!(!entry.add_version(altered.clone()));
+        altered.serialize_id().unwrap();
+        assert!(!entry.add_version(altered));
+        let unsigned = nostr_minions::nostro2::NostrNote {
+            id: None,
+            sig: None,
+            ..version(&game, 4_000)
+        };
+        assert!(!entry.add_version(unsigned));
+        assert_eq!(entry.history.len(), 2);
+
+        let mut draft = crate::idb::RookyGameEntry::new(
+            nostr_minions::nostro2::NostrNote { id: None, ..first },
+            crate::idb::GameOrigin::Annotated,
+        );
+        assert!(draft.add_version(second));
+        assert!(draft.add_version(third));
+        assert_eq!(draft.history.len(), 2);
+    }
+}
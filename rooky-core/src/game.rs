@@ -8,11 +8,22 @@ use shakmaty::Position;
 /// the STR is the common ground that all programs should follow for public data interchange.
 ///
 ///
-/// We should define a Rooky Tag Roster (RTR) for the protocol to use.
-/// This could include tags for black and white pubkeys
-/// and other nostr-specific information.
-/// We also include a set of moves for the game.
-#[derive(Debug, PartialEq, Eq, Clone)]
+/// On top of it, the Rooky Tag Roster (RTR) ties the game to Nostr: the pubkeys of
+/// both players, relay hints and the client that published it. See [`crate::rtr`].
+/// We also include the moves of the game, as a main line where every move can hold
+/// its own variations.
+///
+/// # Serialization
+///
+/// Games also have a `serde` representation, to exchange them as JSON without going
+/// through PGN. Every field keeps its name, and values are written as in PGN where
+/// there is a notation for them: moves in SAN like `"Nf3"`, the outcome as `"1-0"`,
+/// the starting position as a FEN, and the time control, termination, pubkeys and
+/// relays as in their tags. The date is ISO 8601, see [`crate::pgn_standards::PgnDate`].
+/// NAGs are their numbers, clock times are milliseconds, and the other tags are a map
+/// from name to value. Missing fields take their default value.
+#[derive(Debug, PartialEq, Eq, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
 pub struct RookyGame {
     /// The Event tag value should be reasonably descriptive. Abbreviations are to be avoided unless absolutely necessary.
     /// A consistent event naming should be used to help facilitate database scanning. If the name of the event is unknown,
@@ -48,32 +59,74 @@ pub struct RookyGame {
     /// integer round numbers separated by periods. The leftmost integer represents the most significant round and
     /// succeeding integers represent round numbers in descending hierarchical order.
     pub round: crate::pgn_standards::PgnRound,
-    pub date: chrono::NaiveDate,
+    /// Date the game started, parts of it may be unknown.
+    pub date: crate::pgn_standards::PgnDate,
     pub white: String,
     pub black: String,
-    pub outcome: shakmaty::Outcome,
-    pub moves: Vec<shakmaty::san::SanPlus>,
+    /// Result of the game, `*` until it is finished.
+    pub outcome: crate::pgn_standards::PgnResult,
+    /// Rules of the game, from the `Variant` tag. Standard chess when missing.
+    pub variant: crate::pgn_standards::PgnVariant,
+    /// Position the game starts from, given by the `[SetUp "1"]` and `[FEN "..."]` tags.
+    /// Games without it start from the standard initial position.
+    pub starting_position: Option<shakmaty::fen::Fen>,
+    /// Clock settings of the game, from the `TimeControl` tag.
+    pub time_control: Option<crate::pgn_standards::TimeControl>,
+    /// How the game ended, from the `Termination` tag.
+    pub termination: Option<crate::pgn_standards::PgnTermination>,
+    /// Nostr identity of the white player, from the `WhitePubkey` tag.
+    pub white_pubkey: Option<crate::rtr::NostrPubkey>,
+    /// Nostr identity of the black player, from the `BlackPubkey` tag.
+    pub black_pubkey: Option<crate::rtr::NostrPubkey>,
+    /// Relays where the game and its players can be found, from the `Relays` tag.
+    pub relays: Vec<crate::rtr::RelayUrl>,
+    /// Application that published the game, from the `Client` tag.
+    pub client: Option<String>,
+    /// Every other tag found in the PGN, like `ECO` or `WhiteElo`, in their original order.
+    pub tags: crate::headers::PgnTags,
+    pub moves: Vec<crate::RookyMove>,
 }
 impl TryFrom<nostr_minions::nostro2::NostrNote> for RookyGame {
     type Error = crate::errors::ChessError;
 
     fn try_from(value: nostr_minions::nostro2::NostrNote) -> Result<Self, Self::Error> {
-        Self::try_from(value.content.as_bytes())
+        Self::try_from(&value)
     }
 }
+impl TryFrom<&nostr_minions::nostro2::NostrNote> for RookyGame {
+    type Error = crate::errors::ChessError;
+
+    fn try_from(value: &nostr_minions::nostro2::NostrNote) -> Result<Self, Self::Error> {
+        if !crate::events::is_game_kind(value.kind) {
+            return Err(crate::errors::ChessError::InvalidValue {
+                kind: "event kind",
+                value: value.kind.to_string(),
+            });
+        }
+        let game = Self::try_from(value.content.as_bytes())?;
+        // Notes share games once they are played, whatever their kind.
+        if game.moves.is_empty() {
+            return Err(crate::errors::ChessError::EmptyGame);
+        }
+        Ok(game)
+    }
+}
+/// Publishes the game as a NIP-64 event, see [`crate::events`].
 impl From<RookyGame> for nostr_minions::nostro2::NostrNote {
     fn from(game: RookyGame) -> Self {
-        Self {
+        let mut note = Self {
             content: game.to_pgn(),
-            kind: 1,
+            kind: crate::events::GAME_KIND,
+            // Games without a known year are dated when they are published, and relays
+            // reject timestamps before 1970 so older games are dated at its start.
             created_at: game
                 .date
-                .and_hms_opt(0, 0, 0)
-                .expect("Invalid date")
-                .and_utc()
-                .timestamp(),
+                .timestamp()
+                .map_or_else(|| chrono::Utc::now().timestamp(), |time| time.max(0)),
             ..Default::default()
-        }
+        };
+        note.tags.0 = crate::events::game_tags(&game);
+        note
     }
 }
 impl Default for RookyGame {
@@ -82,10 +135,19 @@ impl Default for RookyGame {
             event: crate::pgn_standards::PgnEvent::Casual,
             site: crate::pgn_standards::PgnSite::Unknown,
             round: crate::pgn_standards::PgnRound::Unknown,
-            date: chrono::Local::now().date_naive(),
+            date: chrono::Local::now().date_naive().into(),
             white: String::new(),
             black: String::new(),
-            outcome: shakmaty::Outcome::Draw,
+            outcome: crate::pgn_standards::PgnResult::Unknown,
+            variant: crate::pgn_standards::PgnVariant::Standard,
+            starting_position: None,
+            time_control: None,
+            termination: None,
+            white_pubkey: None,
+            black_pubkey: None,
+            relays: Vec::new(),
+            client: None,
+            tags: crate::headers::PgnTags::default(),
             moves: Vec::new(),
         }
     }
@@ -108,7 +170,7 @@ impl RookyGame {
         self
     }
     #[must_use]
-    pub const fn add_date(mut self, date: chrono::NaiveDate) -> Self {
+    pub const fn add_date(mut self, date: crate::pgn_standards::PgnDate) -> Self {
         self.date = date;
         self
     }
@@ -123,55 +185,467 @@ impl RookyGame {
         self
     }
     #[must_use]
-    pub const fn add_result(mut self, result: shakmaty::Outcome) -> Self {
+    pub const fn add_result(mut self, result: crate::pgn_standards::PgnResult) -> Self {
         self.outcome = result;
         self
     }
     #[must_use]
+    pub const fn add_variant(mut self, variant: crate::pgn_standards::PgnVariant) -> Self {
+        self.variant = variant;
+        self
+    }
+    #[must_use]
+    pub fn add_time_control(mut self, time_control: crate::pgn_standards::TimeControl) -> Self {
+        self.time_control = Some(time_control);
+        self
+    }
+    #[must_use]
+    pub const fn add_termination(
+        mut self,
+        termination: crate::pgn_standards::PgnTermination,
+    ) -> Self {
+        self.termination = Some(termination);
+        self
+    }
+    #[must_use]
+    pub const fn add_white_pubkey(mut self, pubkey: crate::rtr::NostrPubkey) -> Self {
+        self.white_pubkey = Some(pubkey);
+        self
+    }
+    #[must_use]
+    pub const fn add_black_pubkey(mut self, pubkey: crate::rtr::NostrPubkey) -> Self {
+        self.black_pubkey = Some(pubkey);
+        self
+    }
+    /// Adds a relay hint, unless the game already has it.
+    #[must_use]
+    pub fn add_relay(mut self, relay: crate::rtr::RelayUrl) -> Self {
+        if !self.relays.contains(&relay) {
+            self.relays.push(relay);
+        }
+        self
+    }
+    #[must_use]
+    pub fn add_client(mut self, client: String) -> Self {
+        self.client = Some(client);
+        self
+    }
+    /// Starts the game from a custom position instead of the standard one.
+    ///
+    /// Add the variant first, the position is checked against its rules.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the FEN does not describe a legal position.
+    pub fn add_starting_position(
+        mut self,
+        fen: shakmaty::fen::Fen,
+    ) -> Result<Self, crate::errors::ChessError> {
+        self.starting_position = Some(fen);
+        self.initial_variant_position()?;
+        Ok(self)
+    }
+    /// Adds a supplemental tag, replacing its value if the game already has it.
+    ///
+    /// Tags handled by the game itself, like the Seven Tag Roster, are ignored.
+    #[must_use]
+    pub fn add_tag(mut self, name: String, value: String) -> Self {
+        if crate::headers::RookyHeader::try_from(name.as_bytes()).is_err() {
+            self.tags.insert(name, value);
+        }
+        self
+    }
+    #[must_use]
     pub fn new_move(mut self, san_plus: shakmaty::san::SanPlus) -> Self {
-        self.moves.push(san_plus);
+        self.moves.push(san_plus.into());
         self
     }
+    /// Plays a move given in SAN at the end of the main line, if it is legal.
+    ///
+    /// The stored move is written the way the position demands, with its check or mate
+    /// suffix, whatever the given SAN had. It is returned as stored.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`crate::errors::ChessError::IllegalMove`] if the move, or a move already on
+    /// the main line, can not be played.
+    pub fn play(
+        &mut self,
+        san: &shakmaty::san::San,
+    ) -> Result<shakmaty::san::SanPlus, crate::errors::ChessError> {
+        let position = self.last_position()?;
+        let chess_move =
+            san.to_move(&position)
+                .map_err(|_| crate::errors::ChessError::IllegalMove {
+                    ply: self.moves.len() + 1,
+                    san: san.to_string(),
+                })?;
+        Ok(self.push_move(position, &chess_move))
+    }
+    /// Plays a move given in UCI notation, like `e2e4` or `e7e8q`, at the end of the main line.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`crate::errors::ChessError::IllegalMove`] if the move, or a move already on
+    /// the main line, can not be played.
+    pub fn play_uci(
+        &mut self,
+        uci: &shakmaty::uci::UciMove,
+    ) -> Result<shakmaty::san::SanPlus, crate::errors::ChessError> {
+        let position = self.last_position()?;
+        let chess_move =
+            uci.to_move(&position)
+                .map_err(|_| crate::errors::ChessError::IllegalMove {
+                    ply: self.moves.len() + 1,
+                    san: uci.to_string(),
+                })?;
+        Ok(self.push_move(position, &chess_move))
+    }
+    fn push_move(
+        &mut self,
+        position: shakmaty::variant::VariantPosition,
+        chess_move: &shakmaty::Move,
+    ) -> shakmaty::san::SanPlus {
+        let san_plus = shakmaty::san::SanPlus::from_move(position, chess_move);
+        self.moves.push(san_plus.clone().into());
+        san_plus
+    }
+    /// Position after the last move of the main line.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the starting position is invalid or a main line move is illegal.
+    pub fn last_position(
+        &self,
+    ) -> Result<shakmaty::variant::VariantPosition, crate::errors::ChessError> {
+        let mut position = self.initial_variant_position()?;
+        for (index, node) in self.moves.iter().enumerate() {
+            position = play_san(position, &node.san, index + 1)?;
+        }
+        Ok(position)
+    }
+    /// Checks that every move of the game, variations included, is legal.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`crate::errors::ChessError::IllegalMove`] for the first move that can not be
+    /// played, or an error if the starting position is invalid.
+    pub fn validate(&self) -> Result<(), crate::errors::ChessError> {
+        validate_line(self.initial_variant_position()?, &self.moves, 1)
+    }
+    /// How the game ended according to its main line, if it did.
+    ///
+    /// The final position is checked for checkmate, stalemate, insufficient material and
+    /// the end rules of the variant, then repetitions and the move counter. The result
+    /// recorded in the game is not taken into account.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the starting position is invalid or a main line move is illegal.
+    pub fn game_end(
+        &self,
+    ) -> Result<Option<crate::termination::GameEnd>, crate::errors::ChessError> {
+        use shakmaty::zobrist::ZobristHash;
+        let hash = |position: &shakmaty::variant::VariantPosition| {
+            position.zobrist_hash::<shakmaty::zobrist::Zobrist64>(shakmaty::EnPassantMode::Legal)
+        };
+        let mut position = self.initial_variant_position()?;
+        let mut history = vec![hash(&position)];
+        for (index, node) in self.moves.iter().enumerate() {
+            position = play_san(position, &node.san, index + 1)?;
+            history.push(hash(&position));
+        }
+        let last = hash(&position);
+        let repetitions = history.iter().filter(|&&seen| seen == last).count();
+        Ok(crate::termination::GameEnd::detect(&position, repetitions))
+    }
+    /// Checks that the recorded result agrees with how the game ended on the board.
+    ///
+    /// Unfinished games (`*`) and games that did not end on the board, by resignation or
+    /// on time, always agree. Draws that can only be claimed agree with any result.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`crate::errors::ChessError::ResultMismatch`] if the game ended on the board
+    /// with another result, or an error if a main line move is illegal.
+    pub fn check_result(&self) -> Result<(), crate::errors::ChessError> {
+        let Some(end) = self.game_end()?.filter(|end| end.is_final()) else {
+            return Ok(());
+        };
+        let detected = crate::pgn_standards::PgnResult::from(end.outcome());
+        if self.outcome.is_finished() && self.outcome != detected {
+            return Err(crate::errors::ChessError::ResultMismatch {
+                recorded: self.outcome.to_string(),
+                detected: detected.to_string(),
+            });
+        }
+        Ok(())
+    }
+    /// Fills in the result of an unfinished game (`*`) that ended on the board.
+    ///
+    /// The termination is set to [`crate::pgn_standards::PgnTermination::Normal`] when
+    /// missing. Draws that can only be claimed are returned but leave the game unchanged.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the starting position is invalid or a main line move is illegal.
+    pub fn detect_outcome(
+        &mut self,
+    ) -> Result<Option<crate::termination::GameEnd>, crate::errors::ChessError> {
+        let end = self.game_end()?;
+        if let Some(end) = end.filter(|end| end.is_final()) {
+            if !self.outcome.is_finished() {
+                self.outcome = end.outcome().into();
+                self.termination
+                    .get_or_insert(crate::pgn_standards::PgnTermination::Normal);
+            }
+        }
+        Ok(end)
+    }
+    /// Writes the game in the PGN export format, see [`PgnExportOptions`].
     #[must_use]
     pub fn to_pgn(&self) -> String {
-        use std::fmt::Write;
+        self.to_pgn_with(&PgnExportOptions::default())
+    }
+    /// Writes the game in PGN, choosing what to include.
+    ///
+    /// Tags come first, the Seven Tag Roster in its standard order and then the others.
+    /// Tag values are escaped, and the movetext is wrapped into lines no longer than
+    /// `options.line_width`.
+    #[must_use]
+    pub fn to_pgn_with(&self, options: &PgnExportOptions) -> String {
         let mut pgn = String::new();
-        writeln!(pgn, "[Event \"{}\"]", self.event).unwrap();
-        writeln!(pgn, "[Site \"{}\"]", self.site).unwrap();
-        writeln!(pgn, "[Round \"{}\"]", self.round).unwrap();
-        writeln!(pgn, "[Date \"{}\"]", self.date.format("%Y.%m.%d")).unwrap();
-        writeln!(pgn, "[White \"{}\"]", self.white).unwrap();
-        writeln!(pgn, "[Black \"{}\"]", self.black).unwrap();
-        writeln!(pgn, "[Result \"{}\"]", self.outcome).unwrap();
-        writeln!(pgn).unwrap();
-        for (move_num, moves) in self.moves.chunks(2).enumerate() {
-            let move_num = move_num + 1;
-            let white_move = moves
-                .first()
-                .map(std::string::ToString::to_string)
-                .unwrap_or_default();
-            let Some(black_move) = moves.get(1).map(std::string::ToString::to_string) else {
-                write!(pgn, "{move_num}. {white_move} ").unwrap();
-                break;
-            };
-            write!(pgn, "{move_num}. {white_move} {black_move} ").unwrap();
+        push_tag(&mut pgn, "Event", &self.event.to_string());
+        push_tag(&mut pgn, "Site", &self.site.to_string());
+        match self.tags.get("Date") {
+            Some(date) if self.date == crate::pgn_standards::PgnDate::UNKNOWN => {
+                push_tag(&mut pgn, "Date", date);
+            }
+            _ => push_tag(&mut pgn, "Date", &self.date.to_string()),
+        }
+        push_tag(&mut pgn, "Round", &self.round.to_string());
+        push_tag(&mut pgn, "White", &self.white);
+        push_tag(&mut pgn, "Black", &self.black);
+        push_tag(&mut pgn, "Result", &self.outcome.to_string());
+        // Without these the moves can not be replayed, even in the reduced format.
+        if self.variant != crate::pgn_standards::PgnVariant::Standard {
+            push_tag(&mut pgn, "Variant", &self.variant.to_string());
         }
-        writeln!(pgn, "{}", self.outcome).unwrap();
+        if let Some(fen) = &self.starting_position {
+            push_tag(&mut pgn, "SetUp", "1");
+            push_tag(&mut pgn, "FEN", &fen.to_string());
+        }
+        if options.tags {
+            self.push_supplemental_tags(&mut pgn);
+        }
+        pgn.push('\n');
+        let mut movetext = Movetext::new(options);
+        movetext.push_line(&self.moves, self.first_ply());
+        movetext.push(&self.outcome.to_string());
+        pgn.push_str(&movetext.wrap());
+        pgn.push('\n');
         pgn
     }
+    fn push_supplemental_tags(&self, pgn: &mut String) {
+        if let Some(time_control) = &self.time_control {
+            push_tag(pgn, "TimeControl", &time_control.to_string());
+        }
+        if let Some(termination) = &self.termination {
+            push_tag(pgn, "Termination", &termination.to_string());
+        }
+        if let Some(pubkey) = &self.white_pubkey {
+            push_tag(pgn, "WhitePubkey", &pubkey.to_string());
+        }
+        if let Some(pubkey) = &self.black_pubkey {
+            push_tag(pgn, "BlackPubkey", &pubkey.to_string());
+        }
+        if !self.relays.is_empty() {
+            let relays = self
+                .relays
+                .iter()
+                .map(crate::rtr::RelayUrl::as_str)
+                .collect::<Vec<_>>();
+            push_tag(pgn, "Relays", &relays.join(" "));
+        }
+        if let Some(client) = &self.client {
+            push_tag(pgn, "Client", client);
+        }
+        // A typed value replaces the raw one read from a tag it could not parse, and the
+        // Date is written with the Seven Tag Roster.
+        let shadowed = |name: &str| {
+            name == "Date"
+                || (name == "TimeControl" && self.time_control.is_some())
+                || (name == "Termination" && self.termination.is_some())
+        };
+        for (name, value) in self.tags.iter().filter(|(name, _)| !shadowed(name)) {
+            push_tag(pgn, name, value);
+        }
+    }
 
     #[must_use]
     pub fn take_back(&mut self) -> Option<pgn_reader::SanPlus> {
-        self.moves.pop()
+        self.moves.pop().map(|last| last.san)
+    }
+
+    /// The moves of the main line, without any variations.
+    #[must_use]
+    pub fn mainline(&self) -> Vec<shakmaty::san::SanPlus> {
+        self.moves.iter().map(|node| node.san.clone()).collect()
+    }
+
+    #[must_use]
+    pub fn get_move(&self, path: &crate::MovePath) -> Option<&crate::RookyMove> {
+        crate::moves::find_line(&self.moves, path)?.get(path.index)
+    }
+
+    pub fn get_move_mut(&mut self, path: &crate::MovePath) -> Option<&mut crate::RookyMove> {
+        crate::moves::find_line_mut(&mut self.moves, path)?.get_mut(path.index)
+    }
+
+    /// Adds a line as an alternative to the move at `path`.
+    ///
+    /// Returns the index of the new variation on that move.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no move at `path` or the line is empty.
+    pub fn add_variation(
+        &mut self,
+        path: &crate::MovePath,
+        line: impl IntoIterator<Item = shakmaty::san::SanPlus>,
+    ) -> Result<usize, crate::errors::ChessError> {
+        let line: Vec<crate::RookyMove> = line.into_iter().map(Into::into).collect();
+        if line.is_empty() {
+            return Err(crate::errors::ChessError::NotFound("No moves found"));
+        }
+        let node = self
+            .get_move_mut(path)
+            .ok_or(crate::errors::ChessError::NotFound("Move not found"))?;
+        node.variations.push(line);
+        Ok(node.variations.len() - 1)
+    }
+
+    /// Removes a variation from the move at `path`, returning its moves.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the move or the variation do not exist.
+    pub fn delete_variation(
+        &mut self,
+        path: &crate::MovePath,
+        variation: usize,
+    ) -> Result<Vec<crate::RookyMove>, crate::errors::ChessError> {
+        let node = self
+            .get_move_mut(path)
+            .ok_or(crate::errors::ChessError::NotFound("Move not found"))?;
+        if variation >= node.variations.len() {
+            return Err(crate::errors::ChessError::NotFound("Variation not found"));
+        }
+        Ok(node.variations.remove(variation))
     }
 
+    /// Makes a variation of the move at `path` the main continuation of its line.
+    ///
+    /// The moves it replaces, from `path` onwards, become the first variation of the
+    /// promoted move, followed by the remaining alternatives.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the move or the variation do not exist.
+    pub fn promote_variation(
+        &mut self,
+        path: &crate::MovePath,
+        variation: usize,
+    ) -> Result<(), crate::errors::ChessError> {
+        let line = crate::moves::find_line_mut(&mut self.moves, path)
+            .filter(|line| path.index < line.len())
+            .ok_or(crate::errors::ChessError::NotFound("Move not found"))?;
+        if variation >= line[path.index].variations.len() {
+            return Err(crate::errors::ChessError::NotFound("Variation not found"));
+        }
+        let mut promoted = line[path.index].variations.remove(variation);
+        let mut demoted = line.split_off(path.index);
+        let mut alternatives = std::mem::take(&mut demoted[0].variations);
+        alternatives.insert(0, demoted);
+        alternatives.append(&mut promoted[0].variations);
+        promoted[0].variations = alternatives;
+        line.append(&mut promoted);
+        Ok(())
+    }
+
+    /// The position before the first move, either the standard one or the one set up by FEN.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the starting position is not a legal position,
+    /// or if the game is not played with the standard chess rules.
+    pub fn initial_position(&self) -> Result<shakmaty::Chess, crate::errors::ChessError> {
+        match self.initial_variant_position()? {
+            shakmaty::variant::VariantPosition::Chess(position) => Ok(position),
+            _ => Err(crate::errors::ChessError::UnsupportedVariant(
+                self.variant.to_string(),
+            )),
+        }
+    }
+
+    /// The position before the first move, following the rules of the game variant.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the starting position is not legal in the variant.
+    pub fn initial_variant_position(
+        &self,
+    ) -> Result<shakmaty::variant::VariantPosition, crate::errors::ChessError> {
+        let rules = self.variant.rules();
+        let Some(fen) = &self.starting_position else {
+            return Ok(shakmaty::variant::VariantPosition::new(rules));
+        };
+        let mode = if self.variant == crate::pgn_standards::PgnVariant::Chess960 {
+            shakmaty::CastlingMode::Chess960
+        } else {
+            shakmaty::CastlingMode::detect(fen.as_setup())
+        };
+        shakmaty::variant::VariantPosition::from_setup(rules, fen.as_setup().clone(), mode).map_err(
+            |e| crate::errors::ChessError::InvalidFen {
+                fen: fen.to_string(),
+                reason: e.to_string(),
+            },
+        )
+    }
+
+    /// Number of half moves played before the first move, according to the starting position.
+    fn first_ply(&self) -> usize {
+        self.starting_position.as_ref().map_or(0, |fen| {
+            let setup = fen.as_setup();
+            let fullmoves = usize::try_from(setup.fullmoves.get()).unwrap_or(1);
+            (fullmoves - 1) * 2 + usize::from(setup.turn == shakmaty::Color::Black)
+        })
+    }
+
+    /// Positions of the main line for games played with the standard chess rules.
+    ///
+    /// Variant games return no positions, use [`Self::variant_positions`] for them.
     #[must_use]
     pub fn game_positions(&self) -> Vec<shakmaty::Chess> {
+        self.variant_positions()
+            .into_iter()
+            .filter_map(|position| match position {
+                shakmaty::variant::VariantPosition::Chess(position) => Some(position),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Positions of the main line, replayed with the rules of the game variant.
+    #[must_use]
+    pub fn variant_positions(&self) -> Vec<shakmaty::variant::VariantPosition> {
         let mut positions = Vec::new();
-        let mut position = shakmaty::Chess::default();
+        let Ok(mut position) = self.initial_variant_position() else {
+            return positions;
+        };
         positions.push(position.clone());
-        self.moves.iter().fold(&mut positions, |pos, san_plus| {
-            let Ok(new_move) = san_plus.san.to_move(&position) else {
+        self.moves.iter().fold(&mut positions, |pos, node| {
+            let Ok(new_move) = node.san.san.to_move(&position) else {
                 return pos;
             };
             let Ok(new_pos) = position.clone().play(&new_move) else {
@@ -184,31 +658,58 @@ impl RookyGame {
         positions
     }
 
-    pub fn opening(&self) -> Option<crate::openings::EcoOpening> {
-        crate::openings::ECO_OPENINGS.iter().find_map(|opening| {
-            let opening_slice = opening.moves.as_slice();
-            if self.moves.len() < opening_slice.len() {
-                return None;
-            }
-            let game_slice = &self.moves[..opening_slice.len()];
-            if game_slice == opening_slice {
-                Some(opening.clone())
-            } else {
-                None
+    /// Link to the game on the site it was played on, from the `Link` tag or else the
+    /// `Site` tag when it holds a web address, as in Lichess exports.
+    #[must_use]
+    pub fn link(&self) -> Option<&str> {
+        if let Some(link) = self.tags.link() {
+            return Some(link);
+        }
+        match &self.site {
+            crate::pgn_standards::PgnSite::Named(site)
+                if site.starts_with("https://") || site.starts_with("http://") =>
+            {
+                Some(site)
             }
-        })
+            _ => None,
+        }
+    }
+
+    /// Deepest known opening position reached by the main line, see [`crate::openings::classify`].
+    ///
+    /// Only games played with the standard chess rules are classified.
+    #[must_use]
+    pub fn opening(&self) -> Option<crate::openings::OpeningMatch> {
+        if self.variant != crate::pgn_standards::PgnVariant::Standard {
+            return None;
+        }
+        crate::openings::classify(&self.game_positions())
     }
 }
 impl TryFrom<&[u8]> for RookyGame {
     type Error = crate::errors::ChessError;
 
     fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
-        let mut reader = pgn_reader::BufferedReader::new(value);
-        let mut game = Self::default();
-        reader.read_game(&mut game)?;
-        if game.moves.is_empty() {
-            return Err(crate::errors::ChessError::NotFound("No moves found"));
-        }
+        let value = normalize_line_comments(value);
+        let mut reader = pgn_reader::BufferedReader::new(value.as_ref());
+        let mut visitor = RookyGameVisitor::default();
+        let game = reader
+            .read_game(&mut visitor)?
+            .ok_or(crate::errors::ChessError::EmptyGame)?
+            .map_err(|error| {
+                let location = visitor
+                    .error_tag
+                    .as_ref()
+                    .and_then(|tag| locate_tag(&value, tag));
+                match location {
+                    Some((line, column)) => crate::errors::ChessError::At {
+                        line,
+                        column,
+                        error: Box::new(error),
+                    },
+                    None => error,
+                }
+            })?;
         Ok(game)
     }
 }
@@ -232,65 +733,523 @@ impl std::fmt::Display for RookyGame {
     }
 }
 
-impl pgn_reader::Visitor for RookyGame {
-    type Result = Vec<shakmaty::san::SanPlus>;
+/// Line and column of the tag pair named `tag`, both counting from 1.
+fn locate_tag(pgn: &[u8], tag: &[u8]) -> Option<(usize, usize)> {
+    pgn.split(|byte| *byte == b'\n')
+        .enumerate()
+        .find_map(|(index, line)| {
+            let indent = line
+                .iter()
+                .take_while(|byte| byte.is_ascii_whitespace())
+                .count();
+            let name = line[indent..].strip_prefix(b"[")?.strip_prefix(tag)?;
+            name.first()
+                .is_some_and(|byte| byte.is_ascii_whitespace() || *byte == b'"')
+                .then_some((index + 1, indent + 1))
+        })
+}
+
+/// Error for a tag value that can not be read.
+fn invalid_tag(tag: &[u8], value: &[u8]) -> crate::errors::ChessError {
+    crate::errors::ChessError::InvalidTag {
+        tag: String::from_utf8_lossy(tag).to_string(),
+        value: String::from_utf8_lossy(value).to_string(),
+    }
+}
+
+/// Plays a stored move, `ply` is only used to report it if it is illegal.
+fn play_san(
+    position: shakmaty::variant::VariantPosition,
+    san: &shakmaty::san::SanPlus,
+    ply: usize,
+) -> Result<shakmaty::variant::VariantPosition, crate::errors::ChessError> {
+    let illegal = || crate::errors::ChessError::IllegalMove {
+        ply,
+        san: san.to_string(),
+    };
+    let chess_move = san.san.to_move(&position).map_err(|_| illegal())?;
+    position.play(&chess_move).map_err(|_| illegal())
+}
+
+/// Replays a line and its variations from the position before its first move.
+fn validate_line(
+    mut position: shakmaty::variant::VariantPosition,
+    line: &[crate::RookyMove],
+    first_ply: usize,
+) -> Result<(), crate::errors::ChessError> {
+    for (index, node) in line.iter().enumerate() {
+        for variation in &node.variations {
+            validate_line(position.clone(), variation, first_ply + index)?;
+        }
+        position = play_san(position, &node.san, first_ply + index)?;
+    }
+    Ok(())
+}
+
+/// What [`RookyGame::to_pgn_with`] writes.
+///
+/// The default is the export format of the PGN standard, with everything the game holds.
+/// [`Self::reduced`] is its reduced export format: the Seven Tag Roster and the main line.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct PgnExportOptions {
+    /// Tags outside the Seven Tag Roster. `Variant`, `SetUp` and `FEN` are always written.
+    pub tags: bool,
+    /// Comments, including the commands like clock times kept in them.
+    pub comments: bool,
+    /// Numeric annotation glyphs.
+    pub nags: bool,
+    /// Recursive annotation variations.
+    pub variations: bool,
+    /// Longest movetext line, only a single longer token exceeds it.
+    pub line_width: usize,
+}
+impl Default for PgnExportOptions {
+    fn default() -> Self {
+        Self {
+            tags: true,
+            comments: true,
+            nags: true,
+            variations: true,
+            line_width: 80,
+        }
+    }
+}
+impl PgnExportOptions {
+    #[must_use]
+    pub const fn reduced() -> Self {
+        Self {
+            tags: false,
+            comments: false,
+            nags: false,
+            variations: false,
+            line_width: 80,
+        }
+    }
+}
+
+/// Appends a tag pair, escaping quotes and backslashes in its value.
+fn push_tag(pgn: &mut String, name: &str, value: &str) {
+    use std::fmt::Write;
+    let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+    writeln!(pgn, "[{name} \"{value}\"]").unwrap();
+}
+
+/// Movetext being written, as words joined by spaces and then wrapped into lines.
+struct Movetext<'a> {
+    options: &'a PgnExportOptions,
+    words: Vec<String>,
+    /// Whether the next word follows an opening parenthesis, without a space.
+    glued: bool,
+}
+impl<'a> Movetext<'a> {
+    const fn new(options: &'a PgnExportOptions) -> Self {
+        Self {
+            options,
+            words: Vec::new(),
+            glued: false,
+        }
+    }
+    fn push(&mut self, word: &str) {
+        match self.words.last_mut() {
+            Some(last) if self.glued => last.push_str(word),
+            _ => self.words.push(word.to_string()),
+        }
+        self.glued = false;
+    }
+    /// Adds a brace comment, a closing brace would end it early so it is dropped.
+    ///
+    /// Comments that fit on a line are kept whole, longer ones are broken between words.
+    fn push_comment(&mut self, comment: &str) {
+        let comment = comment.replace('}', "");
+        let words = comment.split_whitespace().collect::<Vec<_>>();
+        let whole = format!("{{ {} }}", words.join(" "));
+        if whole.chars().count() <= self.options.line_width {
+            self.push(&whole);
+            return;
+        }
+        self.push("{");
+        for word in words {
+            self.push(word);
+        }
+        self.push("}");
+    }
+    /// Adds the moves of a line, and recursively its variations.
+    ///
+    /// `first_ply` is the number of half moves played before the first move of the line.
+    fn push_line(&mut self, line: &[crate::RookyMove], first_ply: usize) {
+        let mut needs_number = true;
+        for (offset, node) in line.iter().enumerate() {
+            let ply = first_ply + offset;
+            let move_num = ply / 2 + 1;
+            if self.options.comments {
+                for comment in &node.comments_before {
+                    self.push_comment(comment);
+                    needs_number = true;
+                }
+            }
+            if ply.is_multiple_of(2) {
+                self.push(&format!("{move_num}."));
+            } else if needs_number {
+                self.push(&format!("{move_num}..."));
+            }
+            self.push(&node.san.to_string());
+            needs_number = false;
+            if self.options.nags {
+                for nag in &node.nags {
+                    self.push(&nag.to_string());
+                }
+            }
+            if self.options.comments {
+                if !node.annotations.is_empty() {
+                    self.push_comment(&node.annotations.to_string());
+                    needs_number = true;
+                }
+                for comment in &node.comments_after {
+                    self.push_comment(comment);
+                    needs_number = true;
+                }
+            }
+            if self.options.variations {
+                for variation in &node.variations {
+                    self.push("(");
+                    self.glued = true;
+                    self.push_line(variation, ply);
+                    if let Some(last) = self.words.last_mut() {
+                        last.push(')');
+                    }
+                    needs_number = true;
+                }
+            }
+        }
+    }
+    /// Joins the words into lines no longer than the line width.
+    fn wrap(&self) -> String {
+        let mut text = String::new();
+        let mut line_length = 0;
+        for word in &self.words {
+            let length = word.chars().count();
+            if line_length > 0 && line_length + 1 + length > self.options.line_width {
+                text.push('\n');
+                line_length = 0;
+            } else if line_length > 0 {
+                text.push(' ');
+                line_length += 1;
+            }
+            text.push_str(word);
+            line_length += length;
+        }
+        text
+    }
+}
+
+/// Rewrites `;` rest-of-line comments as `{}` comments.
+///
+/// `pgn_reader` skips rest-of-line comments without reporting them to the visitor,
+/// so they are turned into brace comments before reading. Tag pairs, escaped lines
+/// and existing brace comments are copied untouched.
+fn normalize_line_comments(pgn: &[u8]) -> std::borrow::Cow<'_, [u8]> {
+    if !pgn.contains(&b';') {
+        return std::borrow::Cow::Borrowed(pgn);
+    }
+    let mut normalized = Vec::with_capacity(pgn.len() + 16);
+    let mut in_brace = false;
+    let mut in_line_comment = false;
+    let mut skip_line = false;
+    let mut line_start = true;
+    for &byte in pgn {
+        if line_start && !in_brace && matches!(byte, b'[' | b'%') {
+            skip_line = true;
+        }
+        line_start = byte == b'\n';
+        if skip_line {
+            skip_line = !line_start;
+            normalized.push(byte);
+            continue;
+        }
+        match byte {
+            b'\n' | b'\r' if in_line_comment => {
+                in_line_comment = false;
+                normalized.push(b'}');
+                normalized.push(byte);
+            }
+            b'{' | b'}' if in_line_comment => {}
+            b';' if !in_brace && !in_line_comment => {
+                in_line_comment = true;
+                normalized.push(b'{');
+            }
+            b'{' if !in_line_comment => {
+                in_brace = true;
+                normalized.push(byte);
+            }
+            b'}' => {
+                in_brace = false;
+                normalized.push(byte);
+            }
+            _ => normalized.push(byte),
+        }
+    }
+    if in_line_comment {
+        normalized.push(b'}');
+    }
+    std::borrow::Cow::Owned(normalized)
+}
+
+/// Builds a [`RookyGame`] while `pgn_reader` walks through a PGN game.
+///
+/// Lines are kept on a stack: the first one is the main line, and every open
+/// variation is pushed on top until its closing parenthesis is read.
+/// Comments read before any move of a line are held back until that move arrives.
+#[derive(Debug, Default)]
+pub struct RookyGameVisitor {
+    game: RookyGame,
+    lines: Vec<Vec<crate::RookyMove>>,
+    pending_comments: Vec<String>,
+    after_move: bool,
+    error: Option<crate::errors::ChessError>,
+    /// Tag holding the value that caused `error`, used to locate it.
+    error_tag: Option<Vec<u8>>,
+    /// Whether a tag pair or a termination marker was read, text without either and
+    /// without moves is not a game.
+    marked: bool,
+}
+impl RookyGameVisitor {
+    /// Leftover comments with no move to precede are kept after the last move instead.
+    fn flush_comments(&mut self) {
+        if self.pending_comments.is_empty() {
+            return;
+        }
+        if let Some(last) = self.lines.last_mut().and_then(|line| line.last_mut()) {
+            last.comments_after.append(&mut self.pending_comments);
+        }
+        self.pending_comments.clear();
+    }
+    /// Keeps the first error found, with the tag it was found in.
+    fn fail(&mut self, tag: &[u8], error: crate::errors::ChessError) {
+        if self.error.is_none() {
+            self.error = Some(error);
+            self.error_tag = Some(tag.to_vec());
+        }
+    }
+    /// Stores a tag the game has no typed field for, so it is exported back as found.
+    fn keep_tag(&mut self, name: &[u8], value: &[u8]) {
+        if let (Ok(name), Ok(value)) = (std::str::from_utf8(name), std::str::from_utf8(value)) {
+            self.game.tags.insert(name.to_string(), value.to_string());
+        }
+    }
+    /// Attaches the variation on top of the stack to the last move of its parent line.
+    fn close_variation(&mut self) {
+        self.flush_comments();
+        self.after_move = false;
+        if self.lines.len() < 2 {
+            return;
+        }
+        let Some(variation) = self.lines.pop() else {
+            return;
+        };
+        if variation.is_empty() {
+            return;
+        }
+        if let Some(parent) = self.lines.last_mut().and_then(|line| line.last_mut()) {
+            parent.variations.push(variation);
+        }
+    }
+}
+
+impl pgn_reader::Visitor for RookyGameVisitor {
+    type Result = Result<RookyGame, crate::errors::ChessError>;
 
     fn begin_game(&mut self) {
-        self.moves = Vec::new();
+        *self = Self {
+            lines: vec![Vec::new()],
+            ..Default::default()
+        };
     }
     fn san(&mut self, san_plus: shakmaty::san::SanPlus) {
-        self.moves.push(san_plus);
+        if let Some(line) = self.lines.last_mut() {
+            let mut node = crate::RookyMove::from(san_plus);
+            node.comments_before = std::mem::take(&mut self.pending_comments);
+            line.push(node);
+            self.after_move = true;
+        }
+    }
+    fn nag(&mut self, nag: pgn_reader::Nag) {
+        if let Some(last) = self.lines.last_mut().and_then(|line| line.last_mut()) {
+            last.nags.push(nag);
+        }
+    }
+    fn comment(&mut self, comment: pgn_reader::RawComment<'_>) {
+        // Comments may be wrapped over several lines, the line breaks are not part of them.
+        let comment = String::from_utf8_lossy(comment.as_bytes())
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        if comment.is_empty() {
+            return;
+        }
+        match self.lines.last_mut().and_then(|line| line.last_mut()) {
+            Some(last) if self.after_move => {
+                let text = last.annotations.extract(&comment);
+                if !text.is_empty() {
+                    last.comments_after.push(text);
+                }
+            }
+            _ => self.pending_comments.push(comment),
+        }
+    }
+    fn end_headers(&mut self) -> pgn_reader::Skip {
+        // The FEN can only be checked once the variant rules are known.
+        if let Err(error) = self.game.initial_variant_position() {
+            self.fail(b"FEN", error);
+        }
+        pgn_reader::Skip(self.error.is_some())
+    }
+    fn begin_variation(&mut self) -> pgn_reader::Skip {
+        self.flush_comments();
+        self.after_move = false;
+        self.lines.push(Vec::new());
+        pgn_reader::Skip(false)
+    }
+    fn end_variation(&mut self) {
+        self.close_variation();
+    }
+    fn outcome(&mut self, _outcome: Option<shakmaty::Outcome>) {
+        self.marked = true;
     }
     fn end_game(&mut self) -> Self::Result {
-        self.moves.clone()
+        while self.lines.len() > 1 {
+            self.close_variation();
+        }
+        self.flush_comments();
+        if let Some(error) = self.error.take() {
+            return Err(error);
+        }
+        let mut game = std::mem::take(&mut self.game);
+        game.moves = self.lines.pop().unwrap_or_default();
+        if game.moves.is_empty() && !self.marked {
+            return Err(crate::errors::ChessError::EmptyGame);
+        }
+        Ok(game)
     }
     #[allow(clippy::too_many_lines)]
     fn header(&mut self, key: &[u8], value: pgn_reader::RawHeader<'_>) {
-        let Ok(key) = crate::headers::RookyHeader::try_from(key) else {
+        self.marked = true;
+        // Tag values escape quotes and backslashes.
+        let value = value.decode();
+        let value = value.as_ref();
+        let Ok(header) = crate::headers::RookyHeader::try_from(key) else {
+            self.keep_tag(key, value);
             return;
         };
-        match key {
+        match header {
             crate::headers::RookyHeader::Date => {
-                let date = value.0.iter().map(|b| *b as char).collect::<String>();
-                self.date = chrono::NaiveDate::parse_from_str(&date, "%Y.%m.%d")
-                    .ok()
-                    .unwrap_or_default();
+                let date = std::str::from_utf8(value)
+                    .unwrap_or_default()
+                    .parse::<crate::pgn_standards::PgnDate>();
+                if let Ok(date) = date {
+                    self.game.date = date;
+                } else {
+                    // Other readers write dates like "?" or "2023.1.5", keep them as found.
+                    self.game.date = crate::pgn_standards::PgnDate::UNKNOWN;
+                    self.keep_tag(key, value);
+                }
             }
             crate::headers::RookyHeader::White => {
-                if let Ok(white) = std::str::from_utf8(value.0) {
-                    self.white = white.to_string();
+                if let Ok(white) = std::str::from_utf8(value) {
+                    self.game.white = white.to_string();
                 }
             }
             crate::headers::RookyHeader::Black => {
-                if let Ok(black) = std::str::from_utf8(value.0) {
-                    self.black = black.to_string();
+                if let Ok(black) = std::str::from_utf8(value) {
+                    self.game.black = black.to_string();
                 }
             }
             crate::headers::RookyHeader::GameResult => {
-                if let Ok(result) = std::str::from_utf8(value.0) {
+                if let Ok(result) = std::str::from_utf8(value) {
                     if let Ok(outcome) = result.parse::<crate::pgn_standards::PgnResult>() {
-                        match outcome {
-                            crate::pgn_standards::PgnResult::Outcome(outcome) => {
-                                self.outcome = outcome;
-                            }
-                            crate::pgn_standards::PgnResult::Unknown => {}
-                        }
+                        self.game.outcome = outcome;
                     }
                 }
             }
             crate::headers::RookyHeader::Event => {
-                if let Ok(event) = std::str::from_utf8(value.0) {
-                    self.event = crate::pgn_standards::PgnEvent::Named(event.to_string());
+                if let Ok(event) = std::str::from_utf8(value) {
+                    self.game.event = crate::pgn_standards::PgnEvent::Named(event.to_string());
                 }
             }
             crate::headers::RookyHeader::Site => {
-                if let Ok(site) = std::str::from_utf8(value.0) {
-                    self.site = crate::pgn_standards::PgnSite::Named(site.to_string());
+                if let Ok(site) = std::str::from_utf8(value) {
+                    self.game.site = crate::pgn_standards::PgnSite::Named(site.to_string());
+                }
+            }
+            crate::headers::RookyHeader::SetUp => {}
+            crate::headers::RookyHeader::Fen => {
+                let fen = shakmaty::fen::Fen::from_ascii(value).map_err(|e| {
+                    crate::errors::ChessError::InvalidFen {
+                        fen: String::from_utf8_lossy(value).to_string(),
+                        reason: e.to_string(),
+                    }
+                });
+                match fen {
+                    Ok(fen) => self.game.starting_position = Some(fen),
+                    Err(error) => self.fail(key, error),
+                }
+            }
+            crate::headers::RookyHeader::Variant => {
+                let variant = std::str::from_utf8(value)
+                    .unwrap_or_default()
+                    .parse::<crate::pgn_standards::PgnVariant>();
+                match variant {
+                    Ok(variant) => self.game.variant = variant,
+                    Err(error) => self.fail(key, error),
+                }
+            }
+            crate::headers::RookyHeader::TimeControl => {
+                match crate::pgn_standards::TimeControl::try_from(value) {
+                    Ok(time_control) => self.game.time_control = Some(time_control),
+                    Err(_) => self.keep_tag(key, value),
+                }
+            }
+            crate::headers::RookyHeader::Termination => {
+                let termination = std::str::from_utf8(value)
+                    .unwrap_or_default()
+                    .parse::<crate::pgn_standards::PgnTermination>();
+                match termination {
+                    Ok(termination) => self.game.termination = Some(termination),
+                    // Some sites write free text here, keep it as it was.
+                    Err(_) => self.keep_tag(key, value),
+                }
+            }
+            crate::headers::RookyHeader::WhitePubkey => {
+                match std::str::from_utf8(value).unwrap_or_default().parse() {
+                    Ok(pubkey) => self.game.white_pubkey = Some(pubkey),
+                    Err(_) => self.fail(key, invalid_tag(key, value)),
+                }
+            }
+            crate::headers::RookyHeader::BlackPubkey => {
+                match std::str::from_utf8(value).unwrap_or_default().parse() {
+                    Ok(pubkey) => self.game.black_pubkey = Some(pubkey),
+                    Err(_) => self.fail(key, invalid_tag(key, value)),
+                }
+            }
+            crate::headers::RookyHeader::Relays => {
+                let relays = std::str::from_utf8(value)
+                    .unwrap_or_default()
+                    .split_whitespace()
+                    .map(str::parse)
+                    .collect::<Result<Vec<_>, _>>();
+                match relays {
+                    Ok(relays) => self.game.relays = relays,
+                    Err(_) => self.fail(key, invalid_tag(key, value)),
+                }
+            }
+            crate::headers::RookyHeader::Client => {
+                if let Ok(client) = std::str::from_utf8(value) {
+                    self.game.client = Some(client.to_string());
                 }
             }
             crate::headers::RookyHeader::Round => {
-                if let Ok(round) = std::str::from_utf8(value.0) {
-                    self.round = crate::pgn_standards::PgnRound::Named(round.to_string());
+                if let Ok(round) = std::str::from_utf8(value) {
+                    self.game.round = crate::pgn_standards::PgnRound::Named(round.to_string());
                 }
             }
         }
@@ -304,13 +1263,18 @@ mod tests {
 
     const PNG_EXAMPLE: &str = r#"[Event "FIDE World Championship"]
 [Site "New York, NY USA"]
-[Round "5.2"]
 [Date "2023.10.15"]
+[Round "5.2"]
 [White "Carlsen, Magnus"]
 [Black "Nakamura, Hikaru"]
 [Result "1-0"]
 
-1. e4 e5 2. Nf3 Nc6 3. Bb5 Nf6 4. O-O Be7 5. Re1 O-O 6. d3 b5 7. Bb3 d6 8. c3 Na5 9. Bc2 c5 10. Nbd2 Nc6 11. Nf1 Re8 12. Ng3 Bf8 13. h3 h6 14. d4 exd4 15. cxd4 cxd4 16. Nxd4 Nxd4 17. Qxd4 Be6 18. Bg5 Qb6 19. Qxb6 axb6 20. Bxf6 gxf6 21. Rad1 Bg7 22. Rxd6 Bxb2 23. Rd2 Bg7 24. Ne2 Be5 25. f4 Bc7 26. e5 fxe5 27. fxe5 Bxe5 28. Nf4 Bf6 29. Nd5 Bxd5 30. Rxd5 Re6 31. Rxb5 Rc8 32. Bb3 Rc1+ 33. Kh2 Rc2 34. Rxb6 Rxa2 35. Rb8+ Kh7 36. Bxf7 Ra1 37. Be8 Rg6 38. Bxg6+ fxg6 39. Rxg6 1-0
+1. e4 e5 2. Nf3 Nc6 3. Bb5 Nf6 4. O-O Be7 5. Re1 O-O 6. d3 b5 7. Bb3 d6 8. c3
+Na5 9. Bc2 c5 10. Nbd2 Nc6 11. Nf1 Re8 12. Ng3 Bf8 13. h3 h6 14. d4 exd4 15.
+cxd4 cxd4 16. Nxd4 Nxd4 17. Qxd4 Be6 18. Bg5 Qb6 19. Qxb6 axb6 20. Bxf6 gxf6 21.
+Rad1 Bg7 22. Rxd6 Bxb2 23. Rd2 Bg7 24. Ne2 Be5 25. f4 Bc7 26. e5 fxe5 27. fxe5
+Bxe5 28. Nf4 Bf6 29. Nd5 Bxd5 30. Rxd5 Re6 31. Rxb5 Rc8 32. Bb3 Rc1+ 33. Kh2 Rc2
+34. Rxb6 Rxa2 35. Rb8+ Kh7 36. Bxf7 Ra1 37. Be8 Rg6 38. Bxg6+ fxg6 39. Rxg6 1-0
 "#;
 
     #[test]
@@ -318,10 +1282,10 @@ mod tests {
         let game = PNG_EXAMPLE.parse::<RookyGame>().unwrap();
         assert_eq!(game.moves.chunks(2).len(), 39);
         assert_eq!(
-            game.outcome,
-            shakmaty::Outcome::Decisive {
+            game.outcome.outcome(),
+            Some(shakmaty::Outcome::Decisive {
                 winner: shakmaty::Color::White
-            }
+            })
         );
         assert_eq!(game.white, "Carlsen, Magnus".to_string());
         assert_eq!(game.black, "Nakamura, Hikaru".to_string());
@@ -338,23 +1302,29 @@ mod tests {
             crate::pgn_standards::PgnRound::Named("5.2".to_string())
         );
         assert_eq!(
-            game.date,
-            chrono::NaiveDate::from_ymd_opt(2023, 10, 15).unwrap()
+            game.date.naive_date(),
+            chrono::NaiveDate::from_ymd_opt(2023, 10, 15)
         );
     }
     #[allow(clippy::too_many_lines)]
     #[test]
     fn test_build_pgn_game() {
         let new_game = RookyGame::default()
-            .add_date(chrono::NaiveDate::from_ymd_opt(2023, 10, 15).unwrap())
+            .add_date(
+                chrono::NaiveDate::from_ymd_opt(2023, 10, 15)
+                    .unwrap()
+                    .into(),
+            )
             .add_site("New York, NY USA".to_string())
             .add_white_name("Carlsen, Magnus".to_string())
             .add_black_name("Nakamura, Hikaru".to_string())
             .add_event("FIDE World Championship".to_string())
             .add_round("5.2".to_string())
-            .add_result(shakmaty::Outcome::Decisive {
-                winner: shakmaty::Color::White,
-            })
+            .add_result(crate::pgn_standards::PgnResult::Outcome(
+                shakmaty::Outcome::Decisive {
+                    winner: shakmaty::Color::White,
+                },
+            ))
             .new_move(shakmaty::san::SanPlus::from_ascii(b"e4").unwrap())
             .new_move(shakmaty::san::SanPlus::from_ascii(b"e5").unwrap())
             .new_move(shakmaty::san::SanPlus::from_ascii(b"Nf3").unwrap())
@@ -444,4 +1414,668 @@ mod tests {
         assert_eq!(new_game.round, example_game.round);
         assert_eq!(new_game.to_pgn(), PNG_EXAMPLE);
     }
+
+    const VARIATIONS_EXAMPLE: &str = r#"[Event "Casual Game"]
+[Site "?"]
+[Date "2024.01.20"]
+[Round "-"]
+[White "White"]
+[Black "Black"]
+[Result "1-0"]
+
+1. e4 e5 (1... c5 2. Nf3 (2. c3 d5) 2... d6) (1... e6) 2. Nf3 Nc6 3. Bb5 1-0
+"#;
+
+    fn san(san: &str) -> shakmaty::san::SanPlus {
+        shakmaty::san::SanPlus::from_ascii(san.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_read_variations() {
+        let game = VARIATIONS_EXAMPLE.parse::<RookyGame>().unwrap();
+        assert_eq!(
+            game.mainline(),
+            vec![san("e4"), san("e5"), san("Nf3"), san("Nc6"), san("Bb5")]
+        );
+        let black_reply = game.get_move(&crate::MovePath::mainline(1)).unwrap();
+        assert_eq!(black_reply.variations.len(), 2);
+        assert_eq!(
+            black_reply.variations[1],
+            vec![crate::RookyMove::new(san("e6"))]
+        );
+        let sicilian = &black_reply.variations[0];
+        assert_eq!(sicilian.len(), 3);
+        assert_eq!(
+            sicilian[1].variations,
+            vec![vec![
+                crate::RookyMove::new(san("c3")),
+                crate::RookyMove::new(san("d5"))
+            ]]
+        );
+        let alapin = crate::MovePath::mainline(1).variation(0, 1).variation(0, 1);
+        assert_eq!(alapin.ply(), 4);
+        assert_eq!(game.get_move(&alapin).unwrap().san, san("d5"));
+        assert_eq!(game.to_pgn(), VARIATIONS_EXAMPLE);
+        assert_eq!(game.to_pgn().parse::<RookyGame>().unwrap(), game);
+    }
+
+    #[test]
+    fn test_edit_variations() {
+        let mut game = RookyGame::default()
+            .new_move(san("e4"))
+            .new_move(san("e5"))
+            .new_move(san("Nf3"));
+        let reply = crate::MovePath::mainline(1);
+        assert_eq!(
+            game.add_variation(&reply, [san("c5"), san("Nf3")]).unwrap(),
+            0
+        );
+        assert_eq!(game.add_variation(&reply, [san("e6")]).unwrap(), 1);
+        assert!(game
+            .add_variation(&crate::MovePath::mainline(5), [san("d4")])
+            .is_err());
+
+        game.promote_variation(&reply, 0).unwrap();
+        assert_eq!(game.mainline(), vec![san("e4"), san("c5"), san("Nf3")]);
+        let promoted = game.get_move(&reply).unwrap();
+        assert_eq!(
+            promoted.variations,
+            vec![
+                vec![
+                    crate::RookyMove::new(san("e5")),
+                    crate::RookyMove::new(san("Nf3"))
+                ],
+                vec![crate::RookyMove::new(san("e6"))],
+            ]
+        );
+
+        let deleted = game.delete_variation(&reply, 1).unwrap();
+        assert_eq!(deleted, vec![crate::RookyMove::new(san("e6"))]);
+        assert!(game.delete_variation(&reply, 1).is_err());
+        assert!(game
+            .to_pgn()
+            .ends_with("1. e4 c5 (1... e5 2. Nf3) 2. Nf3 *\n"));
+    }
+
+    const ANNOTATED_EXAMPLE: &str = r#"[Event "Casual Game"]
+[Site "?"]
+[Date "2024.01.20"]
+[Round "-"]
+[White "White"]
+[Black "Black"]
+[Result "1-0"]
+
+{ Intro } 1. e4 $1 { Best by test } 1... e5 (1... c5 $5 { Sharper }) 2. Nf3 $1
+$14 1-0
+"#;
+
+    #[test]
+    fn test_read_comments_and_nags() {
+        let mut game = ANNOTATED_EXAMPLE.parse::<RookyGame>().unwrap();
+        let first = game.get_move(&crate::MovePath::mainline(0)).unwrap();
+        assert_eq!(first.comments_before, vec!["Intro".to_string()]);
+        assert_eq!(first.comments_after, vec!["Best by test".to_string()]);
+        assert_eq!(first.nags, vec![pgn_reader::Nag::GOOD_MOVE]);
+        let sicilian = &game.moves[1].variations[0][0];
+        assert_eq!(sicilian.nags, vec![pgn_reader::Nag::SPECULATIVE_MOVE]);
+        assert_eq!(sicilian.comments_after, vec!["Sharper".to_string()]);
+        assert_eq!(
+            game.moves[2].nags,
+            vec![pgn_reader::Nag(1), pgn_reader::Nag(14)]
+        );
+        assert_eq!(game.to_pgn(), ANNOTATED_EXAMPLE);
+
+        let reply = game.get_move_mut(&crate::MovePath::mainline(1)).unwrap();
+        reply.add_comment_after("Symmetrical".to_string());
+        reply.add_nag(pgn_reader::Nag::DUBIOUS_MOVE);
+        reply.add_nag(pgn_reader::Nag::DUBIOUS_MOVE);
+        assert!(game
+            .to_pgn()
+            .contains("1... e5 $6 { Symmetrical } (1... c5"));
+        let first = game.get_move_mut(&crate::MovePath::mainline(0)).unwrap();
+        first.clear_comments();
+        assert!(first.remove_nag(&pgn_reader::Nag::GOOD_MOVE));
+        assert!(game.to_pgn().contains("\n\n1. e4 e5 $6"));
+    }
+
+    #[test]
+    fn test_read_glyphs_and_line_comments() {
+        const PGN: &str = "[Event \"Casual; Blitz\"]\n\n1. e4!? e5?? ; Blunder {really}\n2. Qh5 {A\nlong comment} Nc6 1-0\n";
+        let game = PGN.parse::<RookyGame>().unwrap();
+        assert_eq!(
+            game.event,
+            crate::pgn_standards::PgnEvent::Named("Casual; Blitz".to_string())
+        );
+        assert_eq!(game.moves[0].nags, vec![pgn_reader::Nag::SPECULATIVE_MOVE]);
+        assert_eq!(game.moves[1].nags, vec![pgn_reader::Nag::BLUNDER]);
+        assert_eq!(
+            game.moves[1].comments_after,
+            vec!["Blunder really".to_string()]
+        );
+        assert_eq!(
+            game.moves[2].comments_after,
+            vec!["A long comment".to_string()]
+        );
+        assert_eq!(game.mainline().len(), 4);
+    }
+
+    #[test]
+    fn test_read_command_annotations() {
+        const PGN: &str = r#"[Event "Rated Blitz game"]
+[Site "https://lichess.org/abcdefgh"]
+[Result "0-1"]
+
+1. e4 { [%eval 0.17] [%clk 0:03:00] } 1... e5 { [%eval 0.2] [%clk 0:02:58.4] } 2. Qh5?! { Premature. [%eval -0.5] [%clk 0:02:57] [%csl Gf7][%cal Rh5f7] } 2... Nc6 { [%clk 0:02:50] } 0-1"#;
+        let game = PGN.parse::<RookyGame>().unwrap();
+        let first = &game.moves[0].annotations;
+        assert_eq!(first.clock, Some(std::time::Duration::from_mins(3)));
+        assert_eq!(
+            first.eval,
+            Some(crate::annotations::Evaluation::Centipawns(17))
+        );
+        assert!(game.moves[0].comments_after.is_empty());
+        assert_eq!(
+            game.moves[1].annotations.clock,
+            Some(std::time::Duration::from_millis(178_400))
+        );
+        let queen = &game.moves[2];
+        assert_eq!(queen.comments_after, vec!["Premature.".to_string()]);
+        assert_eq!(queen.annotations.squares.len(), 1);
+        assert_eq!(queen.annotations.arrows.len(), 1);
+
+        let exported = game.to_pgn();
+        assert!(exported.replace('\n', " ").contains(
+            "2. Qh5 $6 { [%eval -0.50] [%clk 0:02:57] [%csl Gf7] [%cal Rh5f7] } { Premature. } 2... Nc6"
+        ));
+        let reparsed = exported.parse::<RookyGame>().unwrap();
+        assert_eq!(reparsed.moves, game.moves);
+    }
+
+    const SETUP_EXAMPLE: &str = r#"[Event "Casual Game"]
+[Site "?"]
+[Date "2024.01.20"]
+[Round "-"]
+[White "White"]
+[Black "Black"]
+[Result "0-1"]
+[SetUp "1"]
+[FEN "3r2k1/5ppp/8/8/8/8/5PPP/6K1 b - - 0 31"]
+
+31... Rd1# (31... Rd2 32. h3) 0-1
+"#;
+
+    #[test]
+    fn test_read_custom_starting_position() {
+        let game = SETUP_EXAMPLE.parse::<RookyGame>().unwrap();
+        assert!(game.starting_position.is_some());
+        let positions = game.game_positions();
+        assert_eq!(positions.len(), 2);
+        assert_eq!(positions[0].turn(), shakmaty::Color::Black);
+        assert!(positions[1].is_checkmate());
+        assert!(game.opening().is_none());
+        assert_eq!(game.to_pgn(), SETUP_EXAMPLE);
+    }
+
+    #[test]
+    fn test_reject_invalid_starting_position() {
+        let missing_king = SETUP_EXAMPLE.replace("3r2k1/", "3r4/");
+        assert!(missing_king.parse::<RookyGame>().is_err());
+        let garbage = SETUP_EXAMPLE.replace("3r2k1/5ppp", "not a fen");
+        assert!(garbage.parse::<RookyGame>().is_err());
+
+        let fen: shakmaty::fen::Fen = "8/8/8/8/8/8/8/8 w - - 0 1".parse().unwrap();
+        assert!(RookyGame::default().add_starting_position(fen).is_err());
+        let fen: shakmaty::fen::Fen = "4k3/8/8/8/8/8/8/4K2R w K - 0 1".parse().unwrap();
+        let game = RookyGame::default()
+            .add_starting_position(fen)
+            .unwrap()
+            .new_move(shakmaty::san::SanPlus::from_ascii(b"O-O").unwrap());
+        assert_eq!(game.game_positions().len(), 2);
+    }
+
+    #[test]
+    fn test_read_variant_games() {
+        const CRAZYHOUSE: &str = r#"[Event "Casual Game"]
+[Site "?"]
+[Date "2024.01.20"]
+[Round "-"]
+[White "White"]
+[Black "Black"]
+[Result "1/2-1/2"]
+[Variant "Crazyhouse"]
+
+1. e4 d5 2. exd5 Qxd5 3. Nc3 Qa5 4. Nf3 Nf6 5. P@e5 P@d4 1/2-1/2
+"#;
+        const CHESS960: &str = r#"[Variant "Chess960"]
+[SetUp "1"]
+[FEN "rk4r1/pppppppp/8/8/8/8/PPPPPPPP/RK4R1 w GAga - 0 1"]
+
+1. O-O O-O-O *"#;
+        let game = CRAZYHOUSE.parse::<RookyGame>().unwrap();
+        assert_eq!(game.variant, crate::pgn_standards::PgnVariant::Crazyhouse);
+        assert_eq!(game.variant_positions().len(), 11);
+        assert!(game.game_positions().is_empty());
+        assert!(game.opening().is_none());
+        assert_eq!(game.to_pgn().parse::<RookyGame>().unwrap(), game);
+
+        let game = CHESS960.parse::<RookyGame>().unwrap();
+        let positions = game.game_positions();
+        assert_eq!(positions.len(), 3);
+        let board = positions[2].board();
+        assert_eq!(
+            board.piece_at(shakmaty::Square::G1),
+            Some(shakmaty::Piece {
+                color: shakmaty::Color::White,
+                role: shakmaty::Role::King
+            })
+        );
+        assert_eq!(
+            board.piece_at(shakmaty::Square::D8),
+            Some(shakmaty::Piece {
+                color: shakmaty::Color::Black,
+                role: shakmaty::Role::Rook
+            })
+        );
+
+        let horde = "[Variant \"Horde\"]\n\n1. d5 e6 *"
+            .parse::<RookyGame>()
+            .unwrap();
+        assert_eq!(horde.variant_positions().len(), 3);
+        assert!(horde.initial_position().is_err());
+
+        assert!("[Variant \"Bughouse\"]\n\n1. e4 *"
+            .parse::<RookyGame>()
+            .is_err());
+    }
+
+    #[test]
+    fn test_keep_supplemental_tags() {
+        const LICHESS_EXPORT: &str = r#"[Event "Rated Blitz game"]
+[Site "https://lichess.org/abcd1234"]
+[Date "2024.03.02"]
+[Round "-"]
+[White "alice"]
+[Black "bob"]
+[Result "1-0"]
+[WhiteElo "1912"]
+[BlackElo "1887"]
+[ECO "C20"]
+[Opening "King's Pawn Game"]
+[Annotator "lichess.org"]
+
+1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6 4. Qxf7# 1-0
+"#;
+        let game = LICHESS_EXPORT.parse::<RookyGame>().unwrap();
+        assert_eq!(game.tags.len(), 5);
+        assert_eq!(game.link(), Some("https://lichess.org/abcd1234"));
+        let over_the_board = LICHESS_EXPORT
+            .replace("https://lichess.org/abcd1234", "Linares ESP")
+            .parse::<RookyGame>()
+            .unwrap()
+            .add_tag("GameId".to_string(), "abcd1234".to_string());
+        assert_eq!(over_the_board.link(), None);
+        assert_eq!(over_the_board.tags.game_id(), Some("abcd1234"));
+        assert_eq!(game.tags.white_elo(), Some(1912));
+        assert_eq!(game.tags.black_elo(), Some(1887));
+        assert_eq!(game.tags.eco(), Some("C20"));
+        assert_eq!(game.tags.opening(), Some("King's Pawn Game"));
+        assert_eq!(game.tags.annotator(), Some("lichess.org"));
+        assert_eq!(game.to_pgn(), LICHESS_EXPORT);
+
+        let game = game
+            .add_tag(
+                "Link".to_string(),
+                "https://lichess.org/abcd1234".to_string(),
+            )
+            .add_tag("White".to_string(), "mallory".to_string());
+        assert_eq!(game.white, "alice");
+        assert_eq!(game.tags.link(), Some("https://lichess.org/abcd1234"));
+    }
+
+    #[test]
+    fn test_read_time_control_and_termination() {
+        const BLITZ: &str = r#"[Event "Rated Blitz game"]
+[Site "https://lichess.org/abcd1234"]
+[Date "2024.03.02"]
+[Round "-"]
+[White "alice"]
+[Black "bob"]
+[Result "0-1"]
+[TimeControl "180+2"]
+[Termination "Time forfeit"]
+[ECO "C20"]
+
+1. e4 e5 0-1
+"#;
+        let game = BLITZ.parse::<RookyGame>().unwrap();
+        assert_eq!(
+            game.time_control,
+            Some(crate::pgn_standards::TimeControl::Incremental {
+                base: 180,
+                increment: 2
+            })
+        );
+        assert_eq!(
+            game.termination,
+            Some(crate::pgn_standards::PgnTermination::TimeForfeit)
+        );
+        assert_eq!(game.tags.len(), 1);
+        assert_eq!(game.to_pgn(), BLITZ);
+
+        let game = "[TimeControl \"blitz\"]\n[Termination \"bob won by checkmate\"]\n\n1. f3 e5 2. g4 Qh4# 0-1"
+            .parse::<RookyGame>()
+            .unwrap();
+        assert_eq!(game.termination, None);
+        assert_eq!(game.tags.get("Termination"), Some("bob won by checkmate"));
+        let pgn = game
+            .add_time_control(crate::pgn_standards::TimeControl::Unknown)
+            .add_termination(crate::pgn_standards::PgnTermination::Normal)
+            .to_pgn();
+        assert_eq!(pgn.matches("[Termination ").count(), 1);
+        assert!(pgn.contains("[Termination \"Normal\"]\n"));
+        assert_eq!(pgn.matches("[TimeControl ").count(), 1);
+
+        let game = RookyGame::default()
+            .add_time_control(crate::pgn_standards::TimeControl::SuddenDeath { seconds: 600 })
+            .add_termination(crate::pgn_standards::PgnTermination::Normal);
+        let pgn = game.to_pgn();
+        assert!(pgn.contains("[TimeControl \"600\"]\n[Termination \"Normal\"]\n"));
+    }
+
+    #[test]
+    fn test_read_rooky_tag_roster() {
+        const RTR_EXAMPLE: &str = r#"[Event "Casual Game"]
+[Site "?"]
+[Date "2025.01.04"]
+[Round "-"]
+[White "alice"]
+[Black "bob"]
+[Result "1-0"]
+[WhitePubkey "3bf0c63fcb93463407af97a5e5ee64fa883d107ef9e558472c4eb9aaaefa459d"]
+[BlackPubkey "82341f882b6eabcd2ba7f1ef90aad961cf074af15b9ef44a09f9d2a8fbfbe6a2"]
+[Relays "wss://relay.damus.io wss://nos.lol"]
+[Client "Rooky"]
+
+1. e4 e5 1-0
+"#;
+        let game = RTR_EXAMPLE.parse::<RookyGame>().unwrap();
+        assert_eq!(
+            game.white_pubkey,
+            "npub180cvv07tjdrrgpa0j7j7tmnyl2yr6yr7l8j4s3evf6u64th6gkwsyjh6w6"
+                .parse()
+                .ok()
+        );
+        assert!(game.black_pubkey.is_some());
+        assert_eq!(game.relays.len(), 2);
+        assert_eq!(game.client.as_deref(), Some("Rooky"));
+        assert!(game.tags.is_empty());
+        assert_eq!(game.to_pgn(), RTR_EXAMPLE);
+
+        let npub = RTR_EXAMPLE.replace(
+            "3bf0c63fcb93463407af97a5e5ee64fa883d107ef9e558472c4eb9aaaefa459d",
+            "npub180cvv07tjdrrgpa0j7j7tmnyl2yr6yr7l8j4s3evf6u64th6gkwsyjh6w6",
+        );
+        assert_eq!(npub.parse::<RookyGame>().unwrap(), game);
+
+        assert!(RTR_EXAMPLE
+            .replace("wss://nos.lol", "nos.lol")
+            .parse::<RookyGame>()
+            .is_err());
+        assert!(RTR_EXAMPLE
+            .replace("82341f88", "zz341f88")
+            .parse::<RookyGame>()
+            .is_err());
+    }
+
+    #[test]
+    fn test_read_unfinished_game() {
+        const ONGOING: &str = r#"[Event "Casual Correspondence game"]
+[Site "https://lichess.org/efgh5678"]
+[Date "2025.02.11"]
+[Round "-"]
+[White "alice"]
+[Black "bob"]
+[Result "*"]
+
+1. d4 d5 2. c4 *
+"#;
+        let game = ONGOING.parse::<RookyGame>().unwrap();
+        assert_eq!(game.outcome, crate::pgn_standards::PgnResult::Unknown);
+        assert_eq!(game.outcome.outcome(), None);
+        assert_eq!(game.to_pgn(), ONGOING);
+
+        let just_started = "[Result \"*\"]\n\n*".parse::<RookyGame>().unwrap();
+        assert!(just_started.moves.is_empty());
+        assert!("*".parse::<RookyGame>().unwrap().moves.is_empty());
+        for text in ["hello world", "Good game!\nRematch?"] {
+            let error = text.parse::<RookyGame>().unwrap_err();
+            assert_eq!(error.code(), "empty_game");
+        }
+        // Forfeits are decided without any move.
+        let forfeit = "[Result \"1-0\"]\n\n1-0".parse::<RookyGame>().unwrap();
+        assert!(forfeit.moves.is_empty());
+        assert_eq!(forfeit.outcome.to_string(), "1-0");
+        assert_eq!(
+            RookyGame::default().outcome,
+            crate::pgn_standards::PgnResult::Unknown
+        );
+    }
+
+    #[test]
+    fn test_read_partial_date() {
+        const HISTORICAL: &str = r#"[Event "New York"]
+[Site "New York, NY USA"]
+[Date "1924.??.??"]
+[Round "?"]
+[White "Reti, Richard"]
+[Black "Capablanca, Jose Raul"]
+[Result "1-0"]
+
+1. Nf3 Nf6 1-0
+"#;
+        let game = HISTORICAL.parse::<RookyGame>().unwrap();
+        assert_eq!(game.date.year, Some(1924));
+        assert_eq!(game.date.month, None);
+        assert_eq!(game.to_pgn(), HISTORICAL);
+        let note = nostr_minions::nostro2::NostrNote::from(game);
+        assert_eq!(note.created_at, 0);
+        assert!(note.tags.0.contains(&vec![
+            "l".to_string(),
+            "1924".to_string(),
+            crate::events::DATE_LABEL.to_string()
+        ]));
+
+        let unknown = HISTORICAL
+            .replace("1924.??.??", "????.??.??")
+            .parse::<RookyGame>()
+            .unwrap();
+        assert_eq!(unknown.date, crate::pgn_standards::PgnDate::UNKNOWN);
+        assert!(unknown.to_pgn().contains("[Date \"????.??.??\"]"));
+
+        for raw in ["1924.02.30", "?", "2023.1.5"] {
+            let written = HISTORICAL.replace("1924.??.??", raw);
+            let game = written.parse::<RookyGame>().unwrap();
+            assert_eq!(game.date, crate::pgn_standards::PgnDate::UNKNOWN);
+            assert_eq!(game.tags.get("Date"), Some(raw));
+            assert_eq!(game.to_pgn(), written);
+        }
+    }
+
+    #[test]
+    fn test_export_format() {
+        let mut game = ANNOTATED_EXAMPLE.parse::<RookyGame>().unwrap();
+        game.white = r#"O"Brien, Pat \ Jr"#.to_string();
+        let exported = game.to_pgn();
+        assert!(exported.contains(r#"[White "O\"Brien, Pat \\ Jr"]"#));
+        let reparsed = exported.parse::<RookyGame>().unwrap();
+        assert_eq!(reparsed.white, game.white);
+        assert_eq!(reparsed.moves, game.moves);
+
+        let long = PNG_EXAMPLE.parse::<RookyGame>().unwrap().to_pgn();
+        assert!(long.lines().all(|line| line.len() <= 80));
+        assert!(!long.contains(" \n"));
+
+        let narrow = PgnExportOptions {
+            line_width: 20,
+            ..PgnExportOptions::default()
+        };
+        let movetext = game.to_pgn_with(&narrow);
+        let movetext = movetext.split("\n\n").nth(1).unwrap();
+        assert_eq!(
+            movetext,
+            "{ Intro } 1. e4 $1\n{ Best by test }\n1... e5 (1... c5 $5\n{ Sharper }) 2. Nf3\n$1 $14 1-0\n"
+        );
+        game.moves[0].comments_after[0] = "Best by test, as Fischer\nused to say".to_string();
+        let wrapped = game.to_pgn_with(&narrow);
+        let reparsed = wrapped.parse::<RookyGame>().unwrap();
+        assert_eq!(
+            reparsed.moves[0].comments_after,
+            vec!["Best by test, as Fischer used to say"]
+        );
+        assert_eq!(reparsed.to_pgn_with(&narrow), wrapped);
+        assert_eq!(reparsed.to_pgn(), game.to_pgn());
+
+        game.tags
+            .insert("Annotator".to_string(), "Steinitz".to_string());
+        let reduced = game.to_pgn_with(&PgnExportOptions::reduced());
+        assert!(!reduced.contains("Annotator"));
+        assert!(reduced.ends_with("\n\n1. e4 e5 2. Nf3 1-0\n"));
+    }
+
+    #[test]
+    fn test_play_legal_moves() {
+        let mut game = RookyGame::default();
+        for san in ["e4", "e5", "Qh5", "Nc6", "Bc4", "Nf6"] {
+            game.play(&san.parse().unwrap()).unwrap();
+        }
+        let mate = game.play(&"Qxf7".parse().unwrap()).unwrap();
+        assert_eq!(mate.to_string(), "Qxf7#");
+        assert_eq!(game.moves.len(), 7);
+
+        let mut game = RookyGame::default();
+        let played = game.play_uci(&"e2e4".parse().unwrap()).unwrap();
+        assert_eq!(played, san("e4"));
+        let error = game.play(&"Ke7".parse().unwrap()).unwrap_err();
+        assert!(matches!(
+            error,
+            crate::errors::ChessError::IllegalMove { ply: 2, ref san } if san == "Ke7"
+        ));
+        assert!(game.play_uci(&"e7e4".parse().unwrap()).is_err());
+        assert_eq!(game.moves.len(), 1);
+
+        let mut corrupt = RookyGame::default().new_move(san("e4")).new_move(san("e4"));
+        assert!(matches!(
+            corrupt.play(&"Nf3".parse().unwrap()),
+            Err(crate::errors::ChessError::IllegalMove { ply: 2, .. })
+        ));
+        assert!(matches!(
+            "1. e4 e5 (1... Ke7) 2. Nf3 *"
+                .parse::<RookyGame>()
+                .unwrap()
+                .validate(),
+            Err(crate::errors::ChessError::IllegalMove { ply: 2, .. })
+        ));
+        ANNOTATED_EXAMPLE
+            .parse::<RookyGame>()
+            .unwrap()
+            .validate()
+            .unwrap();
+    }
+
+    #[test]
+    fn test_typed_errors() {
+        let error = "[Event \"?\"]\n[Variant \"Bughouse\"]\n\n1. e4 *"
+            .parse::<RookyGame>()
+            .unwrap_err();
+        assert_eq!(error.code(), "unsupported_variant");
+        assert_eq!(error.location(), Some((2, 1)));
+        assert!(matches!(
+            error.cause(),
+            crate::errors::ChessError::UnsupportedVariant(variant) if variant == "Bughouse"
+        ));
+
+        let error = "[WhitePubkey \"alice\"]\n\n1. e4 *"
+            .parse::<RookyGame>()
+            .unwrap_err();
+        assert!(matches!(
+            error.cause(),
+            crate::errors::ChessError::InvalidTag { tag, value } if tag == "WhitePubkey" && value == "alice"
+        ));
+        assert_eq!(
+            error.to_string(),
+            "Invalid WhitePubkey tag: \"alice\" (line 1, column 1)"
+        );
+
+        assert_eq!("".parse::<RookyGame>().unwrap_err().code(), "empty_game");
+        assert_eq!(
+            "2023.13.01"
+                .parse::<crate::pgn_standards::PgnDate>()
+                .unwrap_err()
+                .code(),
+            "invalid_date"
+        );
+        assert_eq!(
+            "1-1"
+                .parse::<crate::pgn_standards::PgnResult>()
+                .unwrap_err()
+                .code(),
+            "invalid_tag"
+        );
+    }
+
+    #[test]
+    fn test_serialize_json() {
+        const PGN: &str = r#"[Event "Rated Blitz game"]
+[Site "https://lichess.org/abcdefgh"]
+[Date "2025.01.04"]
+[Round "-"]
+[White "alice"]
+[Black "bob"]
+[Result "0-1"]
+[TimeControl "180+2"]
+[WhitePubkey "3bf0c63fcb93463407af97a5e5ee64fa883d107ef9e558472c4eb9aaaefa459d"]
+[WhiteElo "1500"]
+[ECO "C20"]
+
+1. e4 { [%clk 0:03:00] } 1... e5 2. Qh5?! { Premature. [%eval -0.5] [%cal Rh5f7] } (2. Nf3 Nc6) 2... Nc6 0-1
+"#;
+        let game = PGN.parse::<RookyGame>().unwrap();
+        let json = serde_json::to_value(&game).unwrap();
+        assert_eq!(json["date"], "2025-01-04");
+        assert_eq!(json["outcome"], "0-1");
+        assert_eq!(json["time_control"], "180+2");
+        assert_eq!(
+            json["white_pubkey"],
+            "3bf0c63fcb93463407af97a5e5ee64fa883d107ef9e558472c4eb9aaaefa459d"
+        );
+        assert_eq!(
+            json["tags"],
+            serde_json::json!({ "WhiteElo": "1500", "ECO": "C20" })
+        );
+        assert_eq!(json["moves"][0]["san"], "e4");
+        assert_eq!(json["moves"][0]["annotations"]["clock"], 180_000);
+        let queen = &json["moves"][2];
+        assert_eq!(queen["nags"], serde_json::json!([6]));
+        assert_eq!(queen["annotations"]["eval"], "-0.50");
+        assert_eq!(queen["annotations"]["arrows"], serde_json::json!(["Rh5f7"]));
+        assert_eq!(queen["variations"][0][1]["san"], "Nc6");
+
+        let text = serde_json::to_string(&game).unwrap();
+        let tags = text.find("WhiteElo").unwrap();
+        assert!(tags < text.find("ECO").unwrap());
+        assert_eq!(serde_json::from_str::<RookyGame>(&text).unwrap(), game);
+
+        let minimal = serde_json::json!({
+            "white": "alice",
+            "date": "1924",
+            "moves": [{ "san": "e4" }, { "san": "e5" }],
+        });
+        let minimal = serde_json::from_value::<RookyGame>(minimal).unwrap();
+        assert_eq!(minimal.date.to_string(), "1924.??.??");
+        assert_eq!(minimal.outcome, crate::pgn_standards::PgnResult::Unknown);
+        assert!(minimal.validate().is_ok());
+        assert!(
+            serde_json::from_value::<RookyGame>(serde_json::json!({ "outcome": "2-0" })).is_err()
+        );
+    }
 }
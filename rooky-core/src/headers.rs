@@ -8,8 +8,18 @@ pub enum RookyHeader {
     White,
     Black,
     GameResult,
-    // TODO
-    // Defin RTR headers
+    /// Headers to start the game from a custom position
+    SetUp,
+    Fen,
+    Variant,
+    /// Headers describing how the game was played and how it ended
+    TimeControl,
+    Termination,
+    /// Headers of the Rooky Tag Roster, see [`crate::rtr`]
+    WhitePubkey,
+    BlackPubkey,
+    Relays,
+    Client,
 }
 impl TryFrom<&[u8]> for RookyHeader {
     type Error = &'static str;
@@ -22,7 +32,170 @@ impl TryFrom<&[u8]> for RookyHeader {
             b"White" => Ok(Self::White),
             b"Black" => Ok(Self::Black),
             b"Result" => Ok(Self::GameResult),
+            b"SetUp" => Ok(Self::SetUp),
+            b"FEN" => Ok(Self::Fen),
+            b"Variant" => Ok(Self::Variant),
+            b"TimeControl" => Ok(Self::TimeControl),
+            b"Termination" => Ok(Self::Termination),
+            b"WhitePubkey" => Ok(Self::WhitePubkey),
+            b"BlackPubkey" => Ok(Self::BlackPubkey),
+            b"Relays" => Ok(Self::Relays),
+            b"Client" => Ok(Self::Client),
             _ => Err("Invalid header"),
         }
     }
 }
+
+/// Tags outside the Seven Tag Roster, kept in the order they were found.
+///
+/// Imports from Lichess or Chess.com carry many supplemental tags such as `ECO`,
+/// `WhiteElo` or `Link`. They are stored here as written so they can be exported back
+/// untouched, and the most common ones have typed accessors.
+#[derive(Debug, PartialEq, Eq, Clone, Default, Hash)]
+pub struct PgnTags(Vec<(String, String)>);
+impl PgnTags {
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+    /// Sets the value of a tag, keeping its position if it was already present.
+    pub fn insert(&mut self, name: String, value: String) {
+        if let Some((_, existing)) = self.0.iter_mut().find(|(key, _)| *key == name) {
+            *existing = value;
+        } else {
+            self.0.push((name, value));
+        }
+    }
+    /// Removes a tag, returning its value if it was present.
+    pub fn remove(&mut self, name: &str) -> Option<String> {
+        let index = self.0.iter().position(|(key, _)| key == name)?;
+        Some(self.0.remove(index).1)
+    }
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.0.len()
+    }
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+    /// Encyclopaedia of Chess Openings code, like `C65`.
+    #[must_use]
+    pub fn eco(&self) -> Option<&str> {
+        self.get("ECO")
+    }
+    /// Name of the opening, as given by the source.
+    #[must_use]
+    pub fn opening(&self) -> Option<&str> {
+        self.get("Opening")
+    }
+    #[must_use]
+    pub fn white_elo(&self) -> Option<u32> {
+        self.get("WhiteElo")?.parse().ok()
+    }
+    #[must_use]
+    pub fn black_elo(&self) -> Option<u32> {
+        self.get("BlackElo")?.parse().ok()
+    }
+    #[must_use]
+    pub fn annotator(&self) -> Option<&str> {
+        self.get("Annotator")
+    }
+    /// Link to the game on the site it was played on, see also [`crate::RookyGame::link`].
+    #[must_use]
+    pub fn link(&self) -> Option<&str> {
+        self.get("Link")
+    }
+    /// Id of the game on the site it was played on, like `abcd1234` on Lichess.
+    #[must_use]
+    pub fn game_id(&self) -> Option<&str> {
+        self.get("GameId")
+    }
+    #[must_use]
+    pub fn ply_count(&self) -> Option<u32> {
+        self.get("PlyCount")?.parse().ok()
+    }
+}
+impl FromIterator<(String, String)> for PgnTags {
+    fn from_iter<T: IntoIterator<Item = (String, String)>>(iter: T) -> Self {
+        let mut tags = Self::default();
+        for (name, value) in iter {
+            tags.insert(name, value);
+        }
+        tags
+    }
+}
+/// Tags are serialized as a map from name to value, in their original order.
+impl serde::Serialize for PgnTags {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.iter())
+    }
+}
+impl<'de> serde::Deserialize<'de> for PgnTags {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct TagsVisitor;
+        impl<'de> serde::de::Visitor<'de> for TagsVisitor {
+            type Value = PgnTags;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str("a map of tag names to values")
+            }
+            fn visit_map<A: serde::de::MapAccess<'de>>(
+                self,
+                mut map: A,
+            ) -> Result<Self::Value, A::Error> {
+                let mut tags = PgnTags::default();
+                while let Some((name, value)) = map.next_entry()? {
+                    tags.insert(name, value);
+                }
+                Ok(tags)
+            }
+        }
+        deserializer.deserialize_map(TagsVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pgn_tags() {
+        let mut tags = [
+            ("ECO", "C65"),
+            ("WhiteElo", "2850"),
+            ("BlackElo", "?"),
+            ("Annotator", "Kasparov"),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect::<PgnTags>();
+        assert_eq!(tags.eco(), Some("C65"));
+        assert_eq!(tags.white_elo(), Some(2850));
+        assert_eq!(tags.black_elo(), None);
+        assert_eq!(tags.annotator(), Some("Kasparov"));
+        assert_eq!(tags.link(), None);
+        tags.insert("GameId".to_string(), "abcd1234".to_string());
+        assert_eq!(tags.link(), None);
+        assert_eq!(tags.remove("GameId"), Some("abcd1234".to_string()));
+
+        tags.insert("ECO".to_string(), "C67".to_string());
+        tags.insert("Link".to_string(), "https://lichess.org/abc".to_string());
+        let names = tags.iter().map(|(key, _)| key).collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec!["ECO", "WhiteElo", "BlackElo", "Annotator", "Link"]
+        );
+        assert_eq!(tags.eco(), Some("C67"));
+        assert_eq!(tags.remove("BlackElo"), Some("?".to_string()));
+        assert_eq!(tags.len(), 4);
+    }
+}
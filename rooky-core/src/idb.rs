@@ -6,11 +6,71 @@ pub enum GameOrigin {
     Unknown,
 }
 
+/// A game event stored by the application.
+///
+/// Annotated games, see [`crate::events`], are stored by their address so every version
+/// of a game updates the same entry: `note` is the latest version and `history` holds the
+/// ones it replaced.
 #[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
 pub struct RookyGameEntry {
     pub id: String,
     pub note: nostr_minions::nostro2::NostrNote,
     pub origin: GameOrigin,
+    /// Earlier versions of an annotated game, oldest first.
+    #[serde(default)]
+    pub history: Vec<nostr_minions::nostro2::NostrNote>,
+}
+impl RookyGameEntry {
+    /// Entry for a game event, keyed by its address if it has one, or else by its id.
+    #[must_use]
+    pub fn new(note: nostr_minions::nostro2::NostrNote, origin: GameOrigin) -> Self {
+        let id = crate::events::note_address(&note)
+            .or_else(|| note.id.clone())
+            .unwrap_or_default();
+        Self {
+            id,
+            note,
+            origin,
+            history: Vec::new(),
+        }
+    }
+    /// Adds a version of an annotated game, returning whether the entry changed.
+    ///
+    /// The newest version becomes the note, as relays decide: the latest `created_at`,
+    /// then the lowest id. Versions already known, events of other games or authors and
+    /// events not signed by their author are ignored.
+    pub fn add_version(&mut self, note: nostr_minions::nostro2::NostrNote) -> bool {
+        // The address holds the author and the `d` tag of the game.
+        let address = crate::events::note_address(&note);
+        if address.is_none()
+            || address != crate::events::note_address(&self.note)
+            || !crate::events::verify_note(&note)
+        {
+            return false;
+        }
+        if std::iter::once(&self.note)
+            .chain(&self.history)
+            .filter_map(|version| version.id.as_ref())
+            .any(|id| note.id.as_ref() == Some(id))
+        {
+            return false;
+        }
+        if version_order(&note) > version_order(&self.note) {
+            let replaced = std::mem::replace(&mut self.note, note);
+            self.history.push(replaced);
+        } else {
+            self.history.push(note);
+        }
+        self.history
+            .sort_by(|a, b| version_order(a).cmp(&version_order(b)));
+        true
+    }
+}
+/// Orders the versions of an addressable event, newest last.
+fn version_order(
+    note: &nostr_minions::nostro2::NostrNote,
+) -> (i64, std::cmp::Reverse<Option<&str>>) {
+    (note.created_at, std::cmp::Reverse(note.id.as_deref()))
 }
 
 impl From<&RookyGameEntry> for crate::RookyGame {
@@ -41,7 +101,7 @@ impl TryFrom<web_sys::wasm_bindgen::JsValue> for RookyGameEntry {
 }
 impl nostr_minions::browser_api::IdbStoreManager for RookyGameEntry {
     fn key(&self) -> web_sys::wasm_bindgen::JsValue {
-        web_sys::wasm_bindgen::JsValue::from_str(self.note.id.clone().unwrap_or_default().as_str())
+        web_sys::wasm_bindgen::JsValue::from_str(&self.id)
     }
     fn config() -> nostr_minions::browser_api::IdbStoreConfig {
         nostr_minions::browser_api::IdbStoreConfig {
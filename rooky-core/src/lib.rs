@@ -7,10 +7,21 @@
     clippy::nursery
 )]
 
+pub mod annotations;
+pub mod database;
 pub mod errors;
+pub mod events;
 mod game;
 pub mod headers;
 pub mod idb;
+mod moves;
 pub mod openings;
 pub mod pgn_standards;
+pub mod query;
+pub mod repository;
+pub mod rtr;
+mod serialization;
+pub mod sharing;
+pub mod termination;
 pub use game::*;
+pub use moves::{MovePath, RookyMove};
@@ -0,0 +1,125 @@
+/// A single move of a [`crate::RookyGame`] move tree.
+///
+/// Besides the move itself, a node holds the recursive annotation variations (RAV)
+/// that were suggested instead of it, in the order they appear in the PGN.
+/// Each variation is a line of its own, starting at the same ply as this move.
+///
+/// Annotations are kept next to the move they belong to: comments written before
+/// and after it, the numeric annotation glyphs (NAGs) such as `!`, `?!` or `$14`, and
+/// the typed commands embedded in its comments, like clock times and evaluations.
+#[derive(Debug, PartialEq, Eq, Clone, serde::Serialize, serde::Deserialize)]
+pub struct RookyMove {
+    pub san: shakmaty::san::SanPlus,
+    #[serde(default)]
+    pub comments_before: Vec<String>,
+    #[serde(default)]
+    pub comments_after: Vec<String>,
+    #[serde(default, with = "crate::serialization::nags")]
+    pub nags: Vec<pgn_reader::Nag>,
+    #[serde(default)]
+    pub annotations: crate::annotations::MoveAnnotations,
+    #[serde(default)]
+    pub variations: Vec<Vec<Self>>,
+}
+impl From<shakmaty::san::SanPlus> for RookyMove {
+    fn from(san: shakmaty::san::SanPlus) -> Self {
+        Self::new(san)
+    }
+}
+impl RookyMove {
+    #[must_use]
+    pub fn new(san: shakmaty::san::SanPlus) -> Self {
+        Self {
+            san,
+            comments_before: Vec::new(),
+            comments_after: Vec::new(),
+            nags: Vec::new(),
+            annotations: crate::annotations::MoveAnnotations::default(),
+            variations: Vec::new(),
+        }
+    }
+    /// Adds a comment to be shown before the move is played.
+    pub fn add_comment_before(&mut self, comment: String) {
+        self.comments_before.push(comment);
+    }
+    /// Adds a comment about the move, shown right after it.
+    pub fn add_comment_after(&mut self, comment: String) {
+        self.comments_after.push(comment);
+    }
+    /// Removes every comment before and after the move.
+    pub fn clear_comments(&mut self) {
+        self.comments_before.clear();
+        self.comments_after.clear();
+    }
+    /// Adds an annotation glyph, unless the move already has it.
+    pub fn add_nag(&mut self, nag: pgn_reader::Nag) {
+        if !self.nags.contains(&nag) {
+            self.nags.push(nag);
+        }
+    }
+    /// Removes an annotation glyph, returning whether the move had it.
+    pub fn remove_nag(&mut self, nag: &pgn_reader::Nag) -> bool {
+        let before = self.nags.len();
+        self.nags.retain(|existing| existing != nag);
+        before != self.nags.len()
+    }
+}
+
+/// Address of a move inside a [`crate::RookyGame`] move tree.
+///
+/// Paths start on the main line. Every step descends into a variation of the move
+/// found at `index` in the current line, and the final `index` selects the move
+/// within the last line reached.
+///
+/// `MovePath::mainline(4).variation(0, 1)` is the second move of the first variation
+/// suggested instead of the fifth main line ply.
+#[derive(Debug, PartialEq, Eq, Clone, Default, Hash)]
+pub struct MovePath {
+    pub steps: Vec<(usize, usize)>,
+    pub index: usize,
+}
+impl MovePath {
+    #[must_use]
+    pub const fn mainline(index: usize) -> Self {
+        Self {
+            steps: Vec::new(),
+            index,
+        }
+    }
+    /// Descends into `variation` of the current move, selecting the move at `index` in it.
+    #[must_use]
+    pub fn variation(mut self, variation: usize, index: usize) -> Self {
+        self.steps.push((self.index, variation));
+        self.index = index;
+        self
+    }
+    /// Number of half moves played once the addressed move is on the board.
+    #[must_use]
+    pub fn ply(&self) -> usize {
+        self.steps.iter().map(|(index, _)| index).sum::<usize>() + self.index + 1
+    }
+}
+
+/// Follows the path steps and returns the line holding the addressed move.
+pub fn find_line<'a>(mainline: &'a [RookyMove], path: &MovePath) -> Option<&'a [RookyMove]> {
+    path.steps
+        .iter()
+        .try_fold(mainline, |line, (index, variation)| {
+            line.get(*index)?
+                .variations
+                .get(*variation)
+                .map(Vec::as_slice)
+        })
+}
+
+/// Mutable version of [`find_line`].
+pub fn find_line_mut<'a>(
+    mainline: &'a mut Vec<RookyMove>,
+    path: &MovePath,
+) -> Option<&'a mut Vec<RookyMove>> {
+    path.steps
+        .iter()
+        .try_fold(mainline, |line, (index, variation)| {
+            line.get_mut(*index)?.variations.get_mut(*variation)
+        })
+}
@@ -1,58 +1,219 @@
-pub static ECO_OPENINGS: std::sync::LazyLock<Vec<EcoOpening>> = std::sync::LazyLock::new(|| {
-    const OPENING_TABLES: [&str; 5] = [
-        include_str!("openings/a.tsv"),
-        include_str!("openings/b.tsv"),
-        include_str!("openings/c.tsv"),
-        include_str!("openings/d.tsv"),
-        include_str!("openings/e.tsv"),
-    ];
-    OPENING_TABLES
+/// Every line of the ECO opening tables, from `A00` to `E99`.
+///
+/// The table is generated at build time from the TSV files in `src/openings`, see
+/// `build.rs`. Lines are checked there, so reading it needs no parsing.
+pub static ECO_OPENINGS: &[EcoOpening] = &include!(concat!(env!("OUT_DIR"), "/eco_openings.rs"));
+
+/// Zobrist hash of the position each line reaches and the line's index in
+/// [`ECO_OPENINGS`], sorted by hash. When several lines reach the same position,
+/// only the first one is listed.
+#[allow(clippy::unreadable_literal)]
+static POSITION_INDEX: &[(u64, u16)] = &include!(concat!(env!("OUT_DIR"), "/eco_positions.rs"));
+
+/// The opening line that reaches `position`, if any.
+#[must_use]
+pub fn find_position(position: &shakmaty::Chess) -> Option<&'static EcoOpening> {
+    use shakmaty::zobrist::ZobristHash;
+    let hash: shakmaty::zobrist::Zobrist64 = position.zobrist_hash(shakmaty::EnPassantMode::Legal);
+    let found = POSITION_INDEX
+        .binary_search_by_key(&hash.0, |(hash, _)| *hash)
+        .ok()?;
+    ECO_OPENINGS.get(usize::from(POSITION_INDEX[found].1))
+}
+
+/// Opening a game was classified as, see [`classify`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct OpeningMatch {
+    pub opening: &'static EcoOpening,
+    /// Half moves played when the game last reached a known position,
+    /// it left known theory with the next move.
+    pub ply: usize,
+}
+
+/// Finds the deepest known opening among the positions of a game.
+///
+/// Openings are matched by position rather than by move order, so transpositions
+/// get the same name: `1. d4 Nf6 2. c4 e6 3. g3` and `1. c4 e6 2. g3 Nf6 3. d4` are
+/// both a Catalan Opening. `positions` start with the one before the first move.
+#[must_use]
+pub fn classify(positions: &[shakmaty::Chess]) -> Option<OpeningMatch> {
+    positions
         .iter()
-        .flat_map(|table| table.lines().skip(1)) // Skip header for each table
-        .filter_map(|line| {
-            let mut parts = line.split('\t');
-            let mut eco = EcoOpening {
-                code: parts.next()?.to_string(),
-                ..Default::default()
-            };
-            let name_part = parts.next()?;
-            match name_part.split_once(':') {
-                Some((name, variation)) => {
-                    eco.name = name.trim().to_string();
-                    eco.variation = variation.trim().to_string();
-                }
-                None => {
-                    eco.name = name_part.to_string();
-                }
-            }
-            let game_part = parts.next()?;
-            let mut reader = pgn_reader::BufferedReader::new_cursor(game_part.as_bytes());
-            reader.read_game(&mut eco).unwrap()?;
-            Some(eco)
+        .enumerate()
+        .filter_map(|(ply, position)| {
+            Some(OpeningMatch {
+                opening: find_position(position)?,
+                ply,
+            })
         })
-        .collect()
-});
-#[derive(Debug, PartialEq, Eq, Clone, Default)]
-pub struct EcoOpening {
-    pub code: String,
-    pub name: String,
-    pub variation: String,
-    pub moves: Vec<shakmaty::san::SanPlus>,
+        .next_back()
 }
-impl pgn_reader::Visitor for EcoOpening {
-    type Result = Vec<shakmaty::san::SanPlus>;
 
-    fn begin_game(&mut self) {
-        self.moves = Vec::new();
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Hash)]
+pub struct EcoOpening {
+    pub code: &'static str,
+    pub name: &'static str,
+    /// Part of the name after the colon, empty for the main line of an opening.
+    pub variation: &'static str,
+    /// Moves of the line with their numbers, like `1. e4 e5 2. Nf3`.
+    pub pgn: &'static str,
+}
+impl EcoOpening {
+    /// Moves of the line, in SAN.
+    #[must_use]
+    pub fn moves(&self) -> Vec<shakmaty::san::SanPlus> {
+        self.san_tokens()
+            .into_iter()
+            .filter_map(|token| token.parse().ok())
+            .collect()
     }
-    fn san(&mut self, san_plus: shakmaty::san::SanPlus) {
-        self.moves.push(san_plus);
+    /// Whether this line starts with all the moves of `parent`, and goes on after them.
+    #[must_use]
+    pub fn extends(&self, parent: &Self) -> bool {
+        let moves = self.san_tokens();
+        let parent = parent.san_tokens();
+        moves.len() > parent.len() && moves.starts_with(&parent)
     }
-    fn end_game(&mut self) -> Self::Result {
-        self.moves.clone()
+    /// Moves of the line as written, without their numbers.
+    fn san_tokens(&self) -> Vec<&'static str> {
+        // Move numbers are the only tokens that are not SAN, the moves were checked at build time.
+        self.pgn
+            .split_whitespace()
+            .filter(|token| !token.ends_with('.'))
+            .collect()
+    }
+}
+impl std::fmt::Display for EcoOpening {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.variation.is_empty() {
+            write!(f, "{}", self.name)
+        } else {
+            write!(f, "{}: {}", self.name, self.variation)
+        }
     }
 }
 
+/// Lines with the given ECO code, like `C65`.
+#[must_use]
+pub fn by_code(code: &str) -> Vec<&'static EcoOpening> {
+    ECO_OPENINGS
+        .iter()
+        .filter(|opening| opening.code.eq_ignore_ascii_case(code))
+        .collect()
+}
+
+/// Lines with an ECO code in a range, like `"C60"..="C99"` for the Ruy Lopez.
+///
+/// Like [`by_code`], the bounds are compared ignoring case.
+#[must_use]
+pub fn by_code_range<'a>(codes: impl std::ops::RangeBounds<&'a str>) -> Vec<&'static EcoOpening> {
+    let upper = |bound: std::ops::Bound<&&str>| bound.map(|code| code.to_ascii_uppercase());
+    let (start, end) = (upper(codes.start_bound()), upper(codes.end_bound()));
+    let codes = (
+        start.as_ref().map(String::as_str),
+        end.as_ref().map(String::as_str),
+    );
+    ECO_OPENINGS
+        .iter()
+        .filter(|opening| std::ops::RangeBounds::<&str>::contains(&codes, &opening.code))
+        .collect()
+}
+
+/// Every line of an opening, its main lines first, then its variations in table order.
+///
+/// The name is compared ignoring case and accents, so `"Grunfeld Defense"` finds the
+/// lines of the Grünfeld Defense.
+#[must_use]
+pub fn variations(name: &str) -> Vec<&'static EcoOpening> {
+    let name = normalize(name);
+    let mut lines = ECO_OPENINGS
+        .iter()
+        .filter(|opening| normalize(opening.name) == name)
+        .collect::<Vec<_>>();
+    lines.sort_by_key(|opening| !opening.variation.is_empty());
+    lines
+}
+
+/// Lines whose full name contains every word of the query, ignoring case and accents.
+///
+/// Words may be partial, `"sicil najd"` finds the Najdorf lines of the Sicilian Defense.
+/// Results are sorted by relevance: names where the words start a word first, then
+/// shorter names, so the main lines come before their sub-variations.
+#[must_use]
+pub fn search(query: &str) -> Vec<&'static EcoOpening> {
+    let query = normalize(query);
+    let words = query.split_whitespace().collect::<Vec<_>>();
+    if words.is_empty() {
+        return Vec::new();
+    }
+    let mut found = ECO_OPENINGS
+        .iter()
+        .filter_map(|opening| {
+            let full_name = normalize(&opening.to_string());
+            if !words.iter().all(|word| full_name.contains(word)) {
+                return None;
+            }
+            let partial = words
+                .iter()
+                .filter(|word| {
+                    !full_name
+                        .split_whitespace()
+                        .any(|name_word| name_word.starts_with(*word))
+                })
+                .count();
+            Some(((partial, full_name.len()), opening))
+        })
+        .collect::<Vec<_>>();
+    found.sort_by_key(|(rank, _)| *rank);
+    found.into_iter().map(|(_, opening)| opening).collect()
+}
+
+/// Lines that continue `opening` directly, without another known line in between.
+#[must_use]
+pub fn children(opening: &EcoOpening) -> Vec<&'static EcoOpening> {
+    let parent = opening.san_tokens();
+    let descendants = ECO_OPENINGS
+        .iter()
+        .map(|line| (line.san_tokens(), line))
+        .filter(|(moves, _)| moves.len() > parent.len() && moves.starts_with(&parent))
+        .collect::<Vec<_>>();
+    let known = descendants
+        .iter()
+        .map(|(moves, _)| moves.as_slice())
+        .collect::<std::collections::HashSet<_>>();
+    // A line between the two would be one of the descendants, reached by fewer moves.
+    descendants
+        .iter()
+        .filter(|(moves, _)| {
+            !(parent.len() + 1..moves.len()).any(|len| known.contains(&moves[..len]))
+        })
+        .map(|(_, line)| *line)
+        .collect()
+}
+
+/// Lowercase text without accents or apostrophes, other punctuation becomes spaces.
+fn normalize(text: &str) -> String {
+    let mut normalized = String::with_capacity(text.len());
+    for c in text.chars().flat_map(char::to_lowercase) {
+        match c {
+            'à'..='å' => normalized.push('a'),
+            'ç' | 'ć' | 'č' => normalized.push('c'),
+            'è'..='ë' => normalized.push('e'),
+            'ì'..='ï' => normalized.push('i'),
+            'ñ' => normalized.push('n'),
+            'ò'..='ö' | 'ø' => normalized.push('o'),
+            'ù'..='ü' => normalized.push('u'),
+            'š' => normalized.push('s'),
+            'ž' => normalized.push('z'),
+            'ß' => normalized.push_str("ss"),
+            '\'' | '’' => {}
+            c if c.is_alphanumeric() => normalized.push(c),
+            _ => normalized.push(' '),
+        }
+    }
+    normalized.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
 #[cfg(test)]
 mod tests {
     #[test]
@@ -66,17 +227,94 @@ mod tests {
     #[test]
     fn test_find_opening() {
         const TEST_OPENING: &str = "1. e4 g5 2. d4 h6 3. h4 g4";
-        let mut test_eco = super::EcoOpening::default();
-        let mut reader = pgn_reader::BufferedReader::new_cursor(TEST_OPENING.as_bytes());
-        reader.read_game(&mut test_eco).unwrap();
-        println!("{test_eco:?}");
+        let game = TEST_OPENING.parse::<crate::RookyGame>().unwrap();
         let opening = super::ECO_OPENINGS
             .iter()
-            .find(|opening| opening.moves == test_eco.moves);
-        println!("{:?}", test_eco.moves);
-        println!("{opening:?}");
+            .find(|opening| opening.moves() == game.mainline());
         assert_eq!(opening.unwrap().code, "B00");
         assert_eq!(opening.unwrap().name, "Borg Defense");
         assert_eq!(opening.unwrap().variation, "Troon Gambit");
+
+        let position = game.game_positions().pop().unwrap();
+        assert_eq!(super::find_position(&position), opening);
+        assert!(super::find_position(&shakmaty::Chess::default()).is_none());
+    }
+    #[test]
+    fn test_classify_opening() {
+        let classify = |pgn: &str| {
+            let game = pgn.parse::<crate::RookyGame>().unwrap();
+            super::classify(&game.game_positions()).unwrap()
+        };
+        let paris = classify("1. Nh3 d5 2. g3 e5 3. f4 Nc6 4. e3 *");
+        assert_eq!(paris.opening.name, "Amar Opening");
+        assert_eq!(paris.opening.variation, "Paris Gambit");
+        assert_eq!(paris.ply, 5);
+
+        let catalan = classify("1. d4 Nf6 2. c4 e6 3. g3 *");
+        let transposed = classify("1. c4 e6 2. g3 Nf6 3. d4 *");
+        assert_eq!(catalan.opening.name, "Catalan Opening");
+        assert_eq!(transposed, catalan);
+
+        assert!(super::classify(&crate::RookyGame::default().game_positions()).is_none());
+    }
+    #[test]
+    fn test_lookup_by_code() {
+        let ruy_lopez = super::by_code_range("C60"..="C99");
+        assert!(!ruy_lopez.is_empty());
+        assert!(ruy_lopez.iter().all(|opening| opening.name == "Ruy Lopez"));
+        assert_eq!(super::by_code("c65"), super::by_code_range("C65"..="C65"));
+        assert_eq!(super::by_code_range("c60"..="c99"), ruy_lopez);
+        assert_eq!(super::by_code_range("c60"..), super::by_code_range("C60"..));
+        assert!(super::by_code("Z00").is_empty());
+    }
+    #[test]
+    fn test_search_openings() {
+        let grunfeld = super::variations("grunfeld defense");
+        assert_eq!(grunfeld[0].to_string(), "Grünfeld Defense");
+        assert!(grunfeld
+            .iter()
+            .all(|opening| opening.name == "Grünfeld Defense"));
+
+        let najdorf = super::search("SICIL najd");
+        assert_eq!(
+            najdorf[0].to_string(),
+            "Sicilian Defense: Najdorf Variation"
+        );
+        assert!(najdorf
+            .iter()
+            .all(|opening| opening.variation.contains("Najdorf")));
+        assert_eq!(
+            super::search("kings gambit"),
+            super::search("King's Gambit")
+        );
+        assert!(super::search(" ").is_empty());
+    }
+    #[test]
+    fn test_child_lines() {
+        let amar = &super::ECO_OPENINGS[0];
+        let children = super::children(amar);
+        assert!(children.iter().all(|line| line.extends(amar)));
+        assert!(children.iter().any(|line| line.variation == "Paris Gambit"));
+        assert!(!children
+            .iter()
+            .any(|line| line.variation == "Paris Gambit, Gent Gambit"));
+
+        // Whole moves are compared, `Nf3` does not continue a line ending in `N`.
+        let knight = super::EcoOpening {
+            code: "A00",
+            name: "Test",
+            variation: "",
+            pgn: "1. N",
+        };
+        assert!(!super::ECO_OPENINGS.iter().any(|line| line.extends(&knight)));
+        assert!(super::children(&knight).is_empty());
+        let kings_pawn = super::EcoOpening {
+            pgn: "1.  e4",
+            ..knight
+        };
+        assert!(super::children(&kings_pawn)
+            .iter()
+            .all(|line| line.pgn.starts_with("1. e4 ")));
+        assert!(!super::children(&kings_pawn).is_empty());
     }
 }
@@ -53,6 +53,153 @@ impl std::fmt::Display for PgnSite {
     }
 }
 
+/// Date of a game as written in the `Date` tag, `YYYY.MM.DD`.
+///
+/// Any part may be unknown and is then written with question marks, as in `1924.??.??`
+/// or `????.??.??`. Dates sort by year, month and day, unknown parts first.
+///
+/// Nostr notes need an exact timestamp, see [`Self::timestamp`].
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Default, Hash)]
+pub struct PgnDate {
+    pub year: Option<u16>,
+    pub month: Option<u8>,
+    pub day: Option<u8>,
+}
+impl PgnDate {
+    /// A date where every part is unknown, `????.??.??`.
+    pub const UNKNOWN: Self = Self {
+        year: None,
+        month: None,
+        day: None,
+    };
+    /// The exact calendar date, if every part is known.
+    #[must_use]
+    pub fn naive_date(&self) -> Option<chrono::NaiveDate> {
+        chrono::NaiveDate::from_ymd_opt(
+            i32::from(self.year?),
+            u32::from(self.month?),
+            u32::from(self.day?),
+        )
+    }
+    /// The date in ISO 8601, with as much precision as is known.
+    ///
+    /// A full date is `2023-10-15`, `1924.03.??` is `1924-03` and `1924.??.??` is `1924`.
+    /// Without a year there is no ISO date.
+    #[must_use]
+    pub fn to_iso(&self) -> Option<String> {
+        let year = self.year?;
+        Some(match (self.month, self.day) {
+            (Some(month), Some(day)) => format!("{year:04}-{month:02}-{day:02}"),
+            (Some(month), None) => format!("{year:04}-{month:02}"),
+            (None, _) => format!("{year:04}"),
+        })
+    }
+    /// Unix timestamp of the date, used as the `created_at` of the game note.
+    ///
+    /// The date is read as midnight UTC. An unknown month or day stands for the first one,
+    /// so `1924.??.??` maps to the 1st of January 1924, before 1970 the timestamp is
+    /// negative and the note is dated 0 instead. Without a year there is no timestamp.
+    #[must_use]
+    pub fn timestamp(&self) -> Option<i64> {
+        let date = chrono::NaiveDate::from_ymd_opt(
+            i32::from(self.year?),
+            u32::from(self.month.unwrap_or(1)),
+            u32::from(self.day.unwrap_or(1)),
+        )?;
+        Some(date.and_hms_opt(0, 0, 0)?.and_utc().timestamp())
+    }
+}
+impl From<chrono::NaiveDate> for PgnDate {
+    fn from(date: chrono::NaiveDate) -> Self {
+        use chrono::Datelike;
+        Self {
+            year: u16::try_from(date.year()).ok(),
+            month: u8::try_from(date.month()).ok(),
+            day: u8::try_from(date.day()).ok(),
+        }
+    }
+}
+impl std::str::FromStr for PgnDate {
+    type Err = crate::errors::ChessError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || crate::errors::ChessError::InvalidDate(s.to_string());
+        let mut parts = s.split('.');
+        let (Some(year), Some(month), Some(day), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(invalid());
+        };
+        let date = Self {
+            year: parse_date_part(year, 4).map_err(|()| invalid())?,
+            month: parse_date_part(month, 2).map_err(|()| invalid())?,
+            day: parse_date_part(day, 2).map_err(|()| invalid())?,
+        };
+        if date.month.is_some_and(|month| !(1..=12).contains(&month))
+            || date.day.is_some_and(|day| !(1..=31).contains(&day))
+            || (date.year.is_some()
+                && date.month.is_some()
+                && date.day.is_some()
+                && date.naive_date().is_none())
+        {
+            return Err(invalid());
+        }
+        Ok(date)
+    }
+}
+/// Reads one part of a PGN date, `None` if it is unknown.
+fn parse_date_part<T: std::str::FromStr>(part: &str, len: usize) -> Result<Option<T>, ()> {
+    if part.len() != len {
+        return Err(());
+    }
+    if part.chars().all(|c| c == '?') {
+        return Ok(None);
+    }
+    if !part.chars().all(|c| c.is_ascii_digit()) {
+        return Err(());
+    }
+    part.parse().map(Some).map_err(|_| ())
+}
+/// Dates are serialized in ISO 8601, see [`PgnDate::to_iso`], and `null` without a year.
+impl serde::Serialize for PgnDate {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.to_iso() {
+            Some(iso) => serializer.serialize_str(&iso),
+            None => serializer.serialize_none(),
+        }
+    }
+}
+impl<'de> serde::Deserialize<'de> for PgnDate {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let Some(iso) = Option::<std::borrow::Cow<'de, str>>::deserialize(deserializer)? else {
+            return Ok(Self::UNKNOWN);
+        };
+        let mut parts = iso.splitn(3, '-');
+        let year = parts.next().unwrap_or_default();
+        let month = parts.next().unwrap_or("??");
+        let day = parts.next().unwrap_or("??");
+        format!("{year}.{month}.{day}")
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+impl std::fmt::Display for PgnDate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.year {
+            Some(year) => write!(f, "{year:04}.")?,
+            None => write!(f, "????.")?,
+        }
+        match self.month {
+            Some(month) => write!(f, "{month:02}.")?,
+            None => write!(f, "??.")?,
+        }
+        match self.day {
+            Some(day) => write!(f, "{day:02}"),
+            None => write!(f, "??"),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Clone, Eq, Default)]
 pub enum PgnRound {
     #[default]
@@ -78,12 +225,37 @@ impl std::fmt::Display for PgnRound {
     }
 }
 
-#[derive(Debug, PartialEq, Clone, Eq, Default)]
+/// Result of a game, `*` while it is still being played or was left unfinished.
+#[derive(Debug, PartialEq, Clone, Copy, Eq, Default, Hash)]
 pub enum PgnResult {
     #[default]
     Unknown,
     Outcome(shakmaty::Outcome),
 }
+impl PgnResult {
+    /// The outcome of the game, if it has finished.
+    #[must_use]
+    pub const fn outcome(self) -> Option<shakmaty::Outcome> {
+        match self {
+            Self::Unknown => None,
+            Self::Outcome(outcome) => Some(outcome),
+        }
+    }
+    #[must_use]
+    pub const fn is_finished(self) -> bool {
+        matches!(self, Self::Outcome(_))
+    }
+}
+impl From<shakmaty::Outcome> for PgnResult {
+    fn from(outcome: shakmaty::Outcome) -> Self {
+        Self::Outcome(outcome)
+    }
+}
+impl From<Option<shakmaty::Outcome>> for PgnResult {
+    fn from(outcome: Option<shakmaty::Outcome>) -> Self {
+        outcome.map_or(Self::Unknown, Self::Outcome)
+    }
+}
 impl std::str::FromStr for PgnResult {
     type Err = crate::errors::ChessError;
 
@@ -97,10 +269,10 @@ impl std::str::FromStr for PgnResult {
             })),
             "1/2-1/2" => Ok(Self::Outcome(shakmaty::Outcome::Draw)),
             "*" => Ok(Self::Unknown),
-            _ => Err(crate::errors::ChessError::InvalidPgn(std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
-                "Invalid result",
-            ))),
+            _ => Err(crate::errors::ChessError::InvalidTag {
+                tag: "Result".to_string(),
+                value: s.to_string(),
+            }),
         }
     }
 }
@@ -147,10 +319,10 @@ impl std::str::FromStr for PgnTermination {
             "Rules infraction" => Ok(Self::RulesInfraction),
             "Time forfeit" => Ok(Self::TimeForfeit),
             "Unterminated" => Ok(Self::Unterminated),
-            _ => Err(crate::errors::ChessError::InvalidPgn(std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
-                "Invalid termination reason",
-            ))),
+            _ => Err(crate::errors::ChessError::InvalidTag {
+                tag: "Termination".to_string(),
+                value: s.to_string(),
+            }),
         }
     }
 }
@@ -169,6 +341,77 @@ impl std::fmt::Display for PgnTermination {
     }
 }
 
+/// The rules the game is played with, as named by the `Variant` tag in Lichess exports.
+///
+/// Chess960 and games from a custom position follow the standard rules, Chess960 only
+/// changes how castling rights are read.
+#[derive(Debug, PartialEq, Clone, Copy, Eq, Default, Hash)]
+pub enum PgnVariant {
+    #[default]
+    Standard,
+    Chess960,
+    FromPosition,
+    Crazyhouse,
+    Atomic,
+    Antichess,
+    KingOfTheHill,
+    ThreeCheck,
+    RacingKings,
+    Horde,
+}
+impl PgnVariant {
+    #[must_use]
+    pub const fn rules(self) -> shakmaty::variant::Variant {
+        match self {
+            Self::Standard | Self::Chess960 | Self::FromPosition => {
+                shakmaty::variant::Variant::Chess
+            }
+            Self::Crazyhouse => shakmaty::variant::Variant::Crazyhouse,
+            Self::Atomic => shakmaty::variant::Variant::Atomic,
+            Self::Antichess => shakmaty::variant::Variant::Antichess,
+            Self::KingOfTheHill => shakmaty::variant::Variant::KingOfTheHill,
+            Self::ThreeCheck => shakmaty::variant::Variant::ThreeCheck,
+            Self::RacingKings => shakmaty::variant::Variant::RacingKings,
+            Self::Horde => shakmaty::variant::Variant::Horde,
+        }
+    }
+}
+impl std::str::FromStr for PgnVariant {
+    type Err = crate::errors::ChessError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "" | "Standard" | "standard" | "chess" => Ok(Self::Standard),
+            "Chess960" | "chess960" | "Fischerandom" | "Fischer Random" => Ok(Self::Chess960),
+            "From Position" | "fromPosition" => Ok(Self::FromPosition),
+            "Crazyhouse" | "crazyhouse" => Ok(Self::Crazyhouse),
+            "Atomic" | "atomic" => Ok(Self::Atomic),
+            "Antichess" | "antichess" => Ok(Self::Antichess),
+            "King of the Hill" | "kingOfTheHill" | "kingofthehill" => Ok(Self::KingOfTheHill),
+            "Three-check" | "threeCheck" | "3check" => Ok(Self::ThreeCheck),
+            "Racing Kings" | "racingKings" | "racingkings" => Ok(Self::RacingKings),
+            "Horde" | "horde" => Ok(Self::Horde),
+            _ => Err(crate::errors::ChessError::UnsupportedVariant(s.to_string())),
+        }
+    }
+}
+impl std::fmt::Display for PgnVariant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Standard => write!(f, "Standard"),
+            Self::Chess960 => write!(f, "Chess960"),
+            Self::FromPosition => write!(f, "From Position"),
+            Self::Crazyhouse => write!(f, "Crazyhouse"),
+            Self::Atomic => write!(f, "Atomic"),
+            Self::Antichess => write!(f, "Antichess"),
+            Self::KingOfTheHill => write!(f, "King of the Hill"),
+            Self::ThreeCheck => write!(f, "Three-check"),
+            Self::RacingKings => write!(f, "Racing Kings"),
+            Self::Horde => write!(f, "Horde"),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Clone, Eq)]
 pub enum TimeControl {
     Unknown,
@@ -177,7 +420,7 @@ pub enum TimeControl {
     SuddenDeath { seconds: u32 },
     Incremental { base: u32, increment: u32 },
     Sandclock { seconds: u32 },
-    Multiple(Vec<TimeControl>),
+    Multiple(Vec<Self>),
 }
 #[derive(Debug, PartialEq, Eq)]
 pub enum TimeControlParseError {
@@ -186,6 +429,31 @@ pub enum TimeControlParseError {
     InvalidNumber(String),
     UnexpectedField(String),
 }
+impl std::fmt::Display for TimeControlParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::EmptyString => write!(f, "Empty time control"),
+            Self::InvalidFormat(value) => write!(f, "Invalid time control format: {value}"),
+            Self::InvalidNumber(value) => write!(f, "Invalid time control number: {value}"),
+            Self::UnexpectedField(value) => write!(f, "Unexpected time control field: {value}"),
+        }
+    }
+}
+impl std::error::Error for TimeControlParseError {}
+impl From<TimeControlParseError> for crate::errors::ChessError {
+    fn from(error: TimeControlParseError) -> Self {
+        let value = match error {
+            TimeControlParseError::EmptyString => String::new(),
+            TimeControlParseError::InvalidFormat(value)
+            | TimeControlParseError::InvalidNumber(value)
+            | TimeControlParseError::UnexpectedField(value) => value,
+        };
+        Self::InvalidTag {
+            tag: "TimeControl".to_string(),
+            value,
+        }
+    }
+}
 impl TryFrom<&[u8]> for TimeControl {
     type Error = TimeControlParseError;
 
@@ -290,10 +558,86 @@ impl std::fmt::Display for TimeControl {
     }
 }
 
+crate::serialization::serde_as_named!(PgnEvent, PgnSite, PgnRound);
+crate::serialization::serde_as_string!(
+    PgnResult,
+    PgnTermination,
+    PgnVariant,
+    TimeControl,
+    OlympicCountryCode,
+);
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_pgn_date() {
+        let date = "2023.10.15".parse::<PgnDate>().unwrap();
+        assert_eq!(
+            date.naive_date(),
+            chrono::NaiveDate::from_ymd_opt(2023, 10, 15)
+        );
+        assert_eq!(date.to_string(), "2023.10.15");
+
+        let partial = "1924.??.??".parse::<PgnDate>().unwrap();
+        assert_eq!(partial.year, Some(1924));
+        assert_eq!(partial.naive_date(), None);
+        assert_eq!(partial.to_string(), "1924.??.??");
+        assert_eq!(partial.timestamp(), Some(-1_451_692_800));
+
+        assert_eq!("????.??.??".parse::<PgnDate>().unwrap(), PgnDate::UNKNOWN);
+        assert_eq!(PgnDate::UNKNOWN.to_string(), "????.??.??");
+        assert_eq!(PgnDate::UNKNOWN.timestamp(), None);
+
+        assert!("2023.13.01".parse::<PgnDate>().is_err());
+        assert!("2023.02.30".parse::<PgnDate>().is_err());
+        assert!("2023-10-15".parse::<PgnDate>().is_err());
+        assert!("23.10.15".parse::<PgnDate>().is_err());
+    }
+
+    #[test]
+    fn test_sort_pgn_date() {
+        let mut dates = [
+            "2023.10.15",
+            "????.??.??",
+            "2023.??.??",
+            "1924.05.??",
+            "2023.10.02",
+        ]
+        .iter()
+        .map(|date| date.parse::<PgnDate>().unwrap())
+        .collect::<Vec<_>>();
+        dates.sort();
+        let sorted = dates.iter().map(ToString::to_string).collect::<Vec<_>>();
+        assert_eq!(
+            sorted,
+            vec![
+                "????.??.??",
+                "1924.05.??",
+                "2023.??.??",
+                "2023.10.02",
+                "2023.10.15"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_serialize_pgn_date() {
+        for (pgn, iso) in [
+            ("2023.10.15", serde_json::json!("2023-10-15")),
+            ("1924.05.??", serde_json::json!("1924-05")),
+            ("1924.??.??", serde_json::json!("1924")),
+            ("????.??.??", serde_json::Value::Null),
+        ] {
+            let date = pgn.parse::<PgnDate>().unwrap();
+            assert_eq!(serde_json::to_value(date).unwrap(), iso);
+            assert_eq!(serde_json::from_value::<PgnDate>(iso).unwrap(), date);
+        }
+        assert!(serde_json::from_value::<PgnDate>(serde_json::json!("2023-02-30")).is_err());
+        assert!(serde_json::from_value::<PgnDate>(serde_json::json!("2023.10.15")).is_err());
+    }
+
     #[test]
     fn test_parse_unknown_time() {
         assert_eq!("?".parse(), Ok(TimeControl::Unknown));
@@ -720,10 +1064,10 @@ impl std::str::FromStr for OlympicCountryCode {
             "ZAM" => Ok(Self::Zambia),
             "ZIM" => Ok(Self::Zimbabwe),
             "ZRE" => Ok(Self::Zaire),
-            _ => Err(crate::errors::ChessError::InvalidPgn(std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
-                format!("Unknown country code: {code}"),
-            ))),
+            _ => Err(crate::errors::ChessError::InvalidValue {
+                kind: "country code",
+                value: code.to_string(),
+            }),
         }
     }
 }
@@ -0,0 +1,394 @@
+//! Queries for the games published on relays.
+//!
+//! A [`GameQuery`] collects criteria on the tags of game events, see [`crate::events`],
+//! and turns them into a subscription filter for relays.
+//!
+//! Values given for the same criterion are alternatives, a query for the results `1-0`
+//! and `0-1` finds decisive games. Different criteria must all match.
+//!
+//! Relays only compare one value per tag name, and every label is an `l` tag, so the
+//! filter can only hold one kind of label. It holds the one that narrows the results the
+//! most, from the ECO code down to the result, and [`GameQuery::matches`] checks the
+//! events received against the whole query.
+
+/// Labels by how few games share a value, the first one in the query is sent to relays.
+const LABEL_PRIORITY: [&str; 6] = [
+    crate::events::ECO_LABEL,
+    crate::events::OPENING_LABEL,
+    crate::events::EVENT_LABEL,
+    crate::events::PLAYER_LABEL,
+    crate::events::DATE_LABEL,
+    crate::events::RESULT_LABEL,
+];
+
+/// Criteria for the games to request from relays.
+///
+/// ```
+/// use rooky_core::query::GameQuery;
+///
+/// let query = GameQuery::default()
+///     .add_eco_range("C60"..="C99")
+///     .add_result("1-0".parse().unwrap())
+///     .add_limit(50);
+/// let filter = query.filter();
+/// assert_eq!(filter.kinds, Some(vec![64, 30064]));
+/// assert_eq!(filter.limit, Some(50));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GameQuery {
+    authors: Vec<String>,
+    players: Vec<String>,
+    identifiers: Vec<String>,
+    labels: std::collections::BTreeMap<&'static str, Vec<String>>,
+    played_since: Option<chrono::NaiveDate>,
+    played_until: Option<chrono::NaiveDate>,
+    limit: Option<u32>,
+    legacy: bool,
+}
+impl GameQuery {
+    /// Games published by this pubkey.
+    #[must_use]
+    pub fn add_author(mut self, author: crate::rtr::NostrPubkey) -> Self {
+        self.authors.push(author.to_string());
+        self
+    }
+    /// Games where this pubkey played either color.
+    #[must_use]
+    pub fn add_player(mut self, player: crate::rtr::NostrPubkey) -> Self {
+        self.players.push(player.to_string());
+        self
+    }
+    /// Games where a player has this name, as written in the PGN.
+    #[must_use]
+    pub fn add_player_name(self, name: &str) -> Self {
+        self.add_label(crate::events::PLAYER_LABEL, name.to_string())
+    }
+    /// Games played at this event.
+    #[must_use]
+    pub fn add_event(self, event: &str) -> Self {
+        self.add_label(crate::events::EVENT_LABEL, event.to_string())
+    }
+    /// Games with this ECO code, like `B90`.
+    #[must_use]
+    pub fn add_eco(self, code: &str) -> Self {
+        self.add_label(crate::events::ECO_LABEL, code.to_ascii_uppercase())
+    }
+    /// Games with an ECO code in a range, like `"C60"..="C99"` for the Ruy Lopez.
+    #[must_use]
+    pub fn add_eco_range<'a>(self, codes: impl std::ops::RangeBounds<&'a str>) -> Self {
+        let mut codes = crate::openings::by_code_range(codes)
+            .into_iter()
+            .map(|opening| opening.code)
+            .collect::<Vec<_>>();
+        codes.dedup();
+        codes.into_iter().fold(self, Self::add_eco)
+    }
+    /// Games of an opening, in any of its variations, like `Sicilian Defense`.
+    ///
+    /// A variation given with the name, as in `Sicilian Defense: Najdorf Variation`, is
+    /// ignored since events are only labeled with the opening.
+    #[must_use]
+    pub fn add_opening(self, opening: &str) -> Self {
+        let name = opening.split(':').next().unwrap_or_default().trim();
+        self.add_label(crate::events::OPENING_LABEL, name.to_string())
+    }
+    /// Games with this result.
+    #[must_use]
+    pub fn add_result(self, result: crate::pgn_standards::PgnResult) -> Self {
+        self.add_label(crate::events::RESULT_LABEL, result.to_string())
+    }
+    /// Annotated games with this `d` tag, all their versions the relays still have when
+    /// the author is given too.
+    #[must_use]
+    pub fn add_identifier(mut self, identifier: &str) -> Self {
+        self.identifiers.push(identifier.to_string());
+        self
+    }
+    /// Games played on this day or later.
+    ///
+    /// Games are dated by their date label, games without one never match. A partial
+    /// date matches when some day of it does, so a game of `1924` was played since the
+    /// 1st of June 1924.
+    ///
+    /// Relays are only asked for events created since the start of the year, as the
+    /// `created_at` of a game is at most its date, see [`crate::events`].
+    #[must_use]
+    pub const fn add_played_since(mut self, date: chrono::NaiveDate) -> Self {
+        self.played_since = Some(date);
+        self
+    }
+    /// Games played on this day or earlier, see [`Self::add_played_since`].
+    ///
+    /// Annotated games are created when their version is saved, so relays are not
+    /// asked to filter on this date, only [`Self::matches`] checks it.
+    #[must_use]
+    pub const fn add_played_until(mut self, date: chrono::NaiveDate) -> Self {
+        self.played_until = Some(date);
+        self
+    }
+    /// Requests at most this many games from each relay.
+    #[must_use]
+    pub const fn add_limit(mut self, limit: u32) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+    /// Also requests the kind 1 notes games were published as before NIP-64.
+    ///
+    /// These notes have no labels or `p` tags, so they only match queries on authors.
+    #[must_use]
+    pub const fn add_legacy_games(mut self) -> Self {
+        self.legacy = true;
+        self
+    }
+
+    /// Kinds of the events requested.
+    #[must_use]
+    pub fn kinds(&self) -> Vec<u32> {
+        let mut kinds = vec![crate::events::GAME_KIND, crate::events::ANNOTATED_GAME_KIND];
+        if self.legacy {
+            kinds.push(crate::events::LEGACY_GAME_KIND);
+        }
+        kinds
+    }
+    /// The subscription filter to send to relays.
+    ///
+    /// Events received for it can still miss labels of the query, check them with
+    /// [`Self::matches`].
+    #[must_use]
+    pub fn filter(&self) -> nostr_minions::nostro2::NostrSubscription {
+        let mut filter = nostr_minions::nostro2::NostrSubscription {
+            kinds: Some(self.kinds()),
+            authors: (!self.authors.is_empty()).then(|| self.authors.clone()),
+            since: self.played_since.map(|date| {
+                use chrono::Datelike;
+                let new_year = date.with_ordinal(1).unwrap_or(date);
+                Self::timestamp(new_year.and_time(chrono::NaiveTime::MIN))
+            }),
+            limit: self.limit,
+            ..Default::default()
+        };
+        for player in &self.players {
+            filter.add_tag("#p", player);
+        }
+        for identifier in &self.identifiers {
+            filter.add_tag("#d", identifier);
+        }
+        let label = LABEL_PRIORITY
+            .into_iter()
+            .find_map(|namespace| Some((namespace, self.labels.get(namespace)?)));
+        if let Some((namespace, values)) = label {
+            filter.add_tag("#L", namespace);
+            for value in values {
+                filter.add_tag("#l", value);
+            }
+        }
+        filter
+    }
+    /// Whether an event is a game matching every criterion of the query.
+    #[must_use]
+    pub fn matches(&self, note: &nostr_minions::nostro2::NostrNote) -> bool {
+        let tags = &note.tags.0;
+        let has_tag = |name: &str, value: &str, namespace: Option<&str>| {
+            tags.iter().any(|tag| {
+                tag.first().is_some_and(|first| first == name)
+                    && tag.get(1).is_some_and(|tagged| tagged == value)
+                    && namespace
+                        .is_none_or(|namespace| tag.get(2).is_some_and(|label| label == namespace))
+            })
+        };
+        self.kinds().contains(&note.kind)
+            && (self.authors.is_empty() || self.authors.contains(&note.pubkey))
+            && self.played_on(tags)
+            && (self.players.is_empty()
+                || self.players.iter().any(|player| has_tag("p", player, None)))
+            && (self.identifiers.is_empty()
+                || self
+                    .identifiers
+                    .iter()
+                    .any(|identifier| has_tag("d", identifier, None)))
+            && self.labels.iter().all(|(namespace, values)| {
+                values
+                    .iter()
+                    .any(|value| has_tag("l", value, Some(namespace)))
+            })
+    }
+
+    /// Whether the date label of a game falls between the played dates of the query.
+    fn played_on(&self, tags: &[Vec<String>]) -> bool {
+        if self.played_since.is_none() && self.played_until.is_none() {
+            return true;
+        }
+        // Partial dates are compared with the bounds cut to the same precision.
+        let parts = |date: chrono::NaiveDate| {
+            use chrono::Datelike;
+            [
+                i64::from(date.year()),
+                i64::from(date.month()),
+                i64::from(date.day()),
+            ]
+        };
+        tags.iter()
+            .filter(|tag| tag.first().is_some_and(|name| name == "l"))
+            .filter(|tag| {
+                tag.get(2)
+                    .is_some_and(|label| label == crate::events::DATE_LABEL)
+            })
+            .filter_map(|tag| {
+                tag.get(1)?
+                    .split('-')
+                    .map(str::parse)
+                    .collect::<Result<Vec<i64>, _>>()
+                    .ok()
+            })
+            .filter(|date| (1..=3).contains(&date.len()))
+            .any(|date| {
+                self.played_since
+                    .is_none_or(|since| date[..] >= parts(since)[..date.len()])
+                    && self
+                        .played_until
+                        .is_none_or(|until| date[..] <= parts(until)[..date.len()])
+            })
+    }
+    fn add_label(mut self, namespace: &'static str, value: String) -> Self {
+        let values = self.labels.entry(namespace).or_default();
+        if !values.contains(&value) {
+            values.push(value);
+        }
+        self
+    }
+    fn timestamp(time: chrono::NaiveDateTime) -> u64 {
+        // Relays only take timestamps after 1970
+        u64::try_from(time.and_utc().timestamp()).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALICE: &str = "3bf0c63fcb93463407af97a5e5ee64fa883d107ef9e558472c4eb9aaaefa459d";
+    const BOB: &str = "82341f882b6eabcd2ba7f1ef90aad961cf074af15b9ef44a09f9d2a8fbfbe6a2";
+
+    fn published(pgn: &str) -> nostr_minions::nostro2::NostrNote {
+        let game = pgn.parse::<crate::RookyGame>().unwrap();
+        nostr_minions::nostro2::NostrNote {
+            pubkey: ALICE.to_string(),
+            ..game.into()
+        }
+    }
+
+    #[test]
+    fn test_game_filter() {
+        let query = GameQuery::default()
+            .add_author(ALICE.parse().unwrap())
+            .add_player(BOB.parse().unwrap())
+            .add_result("1-0".parse().unwrap())
+            .add_opening("Ruy Lopez: Morphy Defense")
+            .add_played_since(chrono::NaiveDate::from_ymd_opt(2025, 1, 1).unwrap())
+            .add_played_until(chrono::NaiveDate::from_ymd_opt(2025, 1, 31).unwrap())
+            .add_limit(20);
+        let filter = serde_json::to_value(query.filter()).unwrap();
+        assert_eq!(
+            filter,
+            serde_json::json!({
+                "kinds": [64, 30064],
+                "authors": [ALICE],
+                "since": 1_735_689_600,
+                "limit": 20,
+                "#p": [BOB],
+                "#L": ["rooky.opening"],
+                "#l": ["Ruy Lopez"],
+            })
+        );
+
+        let eco = GameQuery::default()
+            .add_eco_range("b20"..="B22")
+            .add_eco("c65")
+            .add_legacy_games()
+            .filter();
+        assert_eq!(eco.kinds, Some(vec![64, 30064, 1]));
+        assert_eq!(eco.tags.unwrap()["#l"], vec!["B20", "B21", "B22", "C65"]);
+    }
+
+    #[test]
+    fn test_match_game_event() {
+        let note = published(&format!(
+            r#"[Event "Casual Game"]
+[Date "2025.01.04"]
+[White "alice"]
+[Black "bob"]
+[Result "1-0"]
+[BlackPubkey "{BOB}"]
+
+1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 1-0
+"#
+        ));
+        let query = GameQuery::default()
+            .add_player(BOB.parse().unwrap())
+            .add_opening("Ruy Lopez")
+            .add_played_since(chrono::NaiveDate::from_ymd_opt(2025, 1, 4).unwrap());
+        assert!(query.matches(&note));
+        assert!(query
+            .clone()
+            .add_result("1-0".parse().unwrap())
+            .matches(&note));
+        assert!(query
+            .clone()
+            .add_result("0-1".parse().unwrap())
+            .add_result("1-0".parse().unwrap())
+            .matches(&note));
+        assert!(!query
+            .clone()
+            .add_result("0-1".parse().unwrap())
+            .matches(&note));
+        assert!(!query.clone().add_eco("B90").matches(&note));
+        assert!(!query
+            .add_played_until(chrono::NaiveDate::from_ymd_opt(2025, 1, 3).unwrap())
+            .matches(&note));
+        assert!(!GameQuery::default()
+            .add_author(BOB.parse().unwrap())
+            .matches(&note));
+
+        let historical = published(
+            r#"[Event "New York"]
+[Date "1924.??.??"]
+[White "Reti, Richard"]
+[Black "Capablanca, Jose Raul"]
+[Result "1-0"]
+
+1. Nf3 Nf6 1-0
+"#,
+        );
+        let day = |year, month, day| chrono::NaiveDate::from_ymd_opt(year, month, day).unwrap();
+        let played = GameQuery::default()
+            .add_played_since(day(1924, 6, 1))
+            .add_played_until(day(1924, 6, 30));
+        assert_eq!(played.filter().since, Some(0));
+        assert_eq!(played.filter().until, None);
+        assert!(played.matches(&historical));
+        assert!(!GameQuery::default()
+            .add_played_since(day(1925, 1, 1))
+            .matches(&historical));
+        assert!(!GameQuery::default()
+            .add_played_until(day(1923, 12, 31))
+            .matches(&historical));
+        let undated = published("[Result \"1-0\"]\n\n1. e4 1-0\n");
+        assert!(GameQuery::default().matches(&undated));
+        assert!(!played.matches(&undated));
+        let annotated = nostr_minions::nostro2::NostrNote {
+            kind: crate::events::ANNOTATED_GAME_KIND,
+            created_at: 1_760_000_000,
+            ..note.clone()
+        };
+        assert!(GameQuery::default()
+            .add_played_until(day(2025, 1, 4))
+            .matches(&annotated));
+
+        let legacy = nostr_minions::nostro2::NostrNote {
+            kind: crate::events::LEGACY_GAME_KIND,
+            ..note
+        };
+        assert!(!GameQuery::default().matches(&legacy));
+        assert!(GameQuery::default().add_legacy_games().matches(&legacy));
+    }
+}
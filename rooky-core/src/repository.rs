@@ -0,0 +1,636 @@
+//! Public games fetched from relays.
+//!
+//! A [`GameRepository`] subscribes to relays with a [`GameQuery`] and streams the games
+//! received as [`RookyGameEntry`] values with the [`GameOrigin::Public`] origin, each event
+//! once even when several relays send it.
+//!
+//! Relays are reached through the [`GameRelay`] trait:
+//!
+//! - [`RelayPoolFeed`] connects it to a [`RelayPool`], like the one of `nostr-minions`.
+//! - [`MemoryRelay`] keeps events in memory, for tests and offline use.
+//!
+//! [`GameOrigin::Public`]: crate::idb::GameOrigin::Public
+//! [`RookyGameEntry`]: crate::idb::RookyGameEntry
+
+use std::task::Poll;
+
+use crate::query::GameQuery;
+
+/// Connection to the relays games are requested from.
+///
+/// Implementations are handles to a shared connection, cloned for every stream.
+pub trait GameRelay: Clone {
+    /// Sends a client message, a subscription or the closing of one, to the relays.
+    fn send(&self, event: nostr_minions::nostro2::NostrClientEvent);
+    /// Polls the next note received for a subscription.
+    ///
+    /// Returns `None` once the subscription is closed.
+    fn poll_note(
+        &self,
+        subscription: &str,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<Option<nostr_minions::nostro2::NostrNote>>;
+}
+
+/// Games published on relays.
+///
+/// ```
+/// use rooky_core::query::GameQuery;
+/// use rooky_core::repository::{GameRepository, MemoryRelay};
+///
+/// let relay = MemoryRelay::default();
+/// let repository = GameRepository::new(relay.clone());
+/// let games = repository.games(GameQuery::default().add_eco("B90"));
+/// assert_eq!(relay.subscriptions().len(), 1);
+/// drop(games);
+/// assert!(relay.subscriptions().is_empty());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameRepository<R> {
+    relay: R,
+}
+impl<R: GameRelay> GameRepository<R> {
+    #[must_use]
+    pub const fn new(relay: R) -> Self {
+        Self { relay }
+    }
+    /// Subscribes to the games matching the query.
+    ///
+    /// The stream stays open for games published later, and closes the subscription
+    /// when dropped.
+    #[must_use]
+    pub fn games(&self, query: GameQuery) -> GameStream<R> {
+        let request = nostr_minions::nostro2::NostrClientEvent::from(query.filter());
+        let nostr_minions::nostro2::NostrClientEvent::Subscribe(_, subscription, _) = &request
+        else {
+            unreachable!("a filter is sent as a subscription")
+        };
+        let subscription = subscription.clone();
+        self.relay.send(request);
+        GameStream {
+            relay: self.relay.clone(),
+            subscription,
+            query,
+            seen: std::collections::HashSet::new(),
+            versions: std::collections::HashMap::new(),
+        }
+    }
+}
+
+/// Games received for a subscription, see [`GameRepository::games`].
+///
+/// Events that are not games, are not signed by their author, do not match the whole
+/// query or do not parse are skipped.
+/// An annotated game is returned again, with its history, for every version received.
+#[derive(Debug)]
+pub struct GameStream<R: GameRelay> {
+    relay: R,
+    subscription: String,
+    query: GameQuery,
+    /// Ids of the events already returned.
+    seen: std::collections::HashSet<String>,
+    /// Annotated games returned, by address.
+    versions: std::collections::HashMap<String, crate::idb::RookyGameEntry>,
+}
+impl<R: GameRelay> GameStream<R> {
+    /// Id of the subscription sent to the relays.
+    #[must_use]
+    pub fn subscription(&self) -> &str {
+        &self.subscription
+    }
+}
+impl<R: GameRelay + Unpin> futures_core::Stream for GameStream<R> {
+    type Item = crate::idb::RookyGameEntry;
+
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            let Some(note) = std::task::ready!(this.relay.poll_note(&this.subscription, cx)) else {
+                return Poll::Ready(None);
+            };
+            let Some(id) = note.id.clone() else {
+                continue;
+            };
+            // Only verified notes are seen, an altered copy of one is skipped with it.
+            if !this.query.matches(&note)
+                || this.seen.contains(&id)
+                || !crate::events::verify_note(&note)
+            {
+                continue;
+            }
+            if crate::RookyGame::try_from(&note).is_err() {
+                continue;
+            }
+            this.seen.insert(id);
+            let entry = crate::idb::RookyGameEntry::new(note, crate::idb::GameOrigin::Public);
+            if let Some(known) = this.versions.get_mut(&entry.id) {
+                known.add_version(entry.note);
+                return Poll::Ready(Some(known.clone()));
+            }
+            if crate::events::note_address(&entry.note).is_some() {
+                this.versions.insert(entry.id.clone(), entry.clone());
+            }
+            return Poll::Ready(Some(entry));
+        }
+    }
+}
+impl<R: GameRelay> Drop for GameStream<R> {
+    fn drop(&mut self) {
+        self.relay
+            .send(nostr_minions::nostro2::NostrClientEvent::close_subscription(&self.subscription));
+    }
+}
+
+/// State of a pool of relay connections, read by a [`RelayPoolFeed`].
+pub trait RelayPool {
+    /// Sends a client message to every relay of the pool.
+    fn send(&self, event: nostr_minions::nostro2::NostrClientEvent);
+    /// Every note received by the pool, once each, in the order they came.
+    ///
+    /// Notes are only ever added at the end.
+    fn notes(&self) -> &[nostr_minions::nostro2::NostrNote];
+}
+/// The relay pool of `nostr-minions`, a Yew context.
+impl RelayPool for nostr_minions::relay_pool::NostrRelayPoolStore {
+    fn send(&self, event: nostr_minions::nostro2::NostrClientEvent) {
+        (**self).send(event);
+    }
+    fn notes(&self) -> &[nostr_minions::nostro2::NostrNote] {
+        &self.unique_notes
+    }
+}
+
+/// [`GameRelay`] over a [`RelayPool`].
+///
+/// Pools like the one of `nostr-minions` are states replaced on every change, so the
+/// feed has to be given the pool each time, as with
+/// `feed.update(nostr_minions::relay_pool::use_nostr_relay_pool())` in a component
+/// keeping the feed in a `use_memo`:
+///
+/// ```
+/// use rooky_core::query::GameQuery;
+/// use rooky_core::repository::{GameRepository, RelayPool, RelayPoolFeed};
+///
+/// #[derive(Default)]
+/// struct Pool(Vec<nostr_minions::nostro2::NostrNote>);
+/// impl RelayPool for Pool {
+///     fn send(&self, _event: nostr_minions::nostro2::NostrClientEvent) {}
+///     fn notes(&self) -> &[nostr_minions::nostro2::NostrNote] {
+///         &self.0
+///     }
+/// }
+///
+/// let feed = RelayPoolFeed::default();
+/// let games = GameRepository::new(feed.clone()).games(GameQuery::default());
+/// feed.update(Pool::default());
+/// ```
+///
+/// The pool does not keep which subscription a note came for, and keeps a note received
+/// again for another one only once, so every stream reads all the notes of the pool from
+/// the first and keeps those matching its query. Each note is read once by each stream,
+/// opening a stream costs a pass over the notes the pool already holds. Messages sent
+/// before the first update are held until then.
+#[derive(Debug)]
+pub struct RelayPoolFeed<P = nostr_minions::relay_pool::NostrRelayPoolStore>(
+    std::rc::Rc<std::cell::RefCell<PoolFeedState<P>>>,
+);
+#[derive(Debug)]
+struct PoolFeedState<P> {
+    pool: Option<P>,
+    pending: Vec<nostr_minions::nostro2::NostrClientEvent>,
+    /// Index of the next note of the pool to return, by subscription.
+    cursors: std::collections::HashMap<String, usize>,
+    wakers: std::collections::HashMap<String, std::task::Waker>,
+}
+impl<P> Default for RelayPoolFeed<P> {
+    fn default() -> Self {
+        Self(std::rc::Rc::new(std::cell::RefCell::new(PoolFeedState {
+            pool: None,
+            pending: Vec::new(),
+            cursors: std::collections::HashMap::new(),
+            wakers: std::collections::HashMap::new(),
+        })))
+    }
+}
+impl<P> Clone for RelayPoolFeed<P> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+impl<P: RelayPool> RelayPoolFeed<P> {
+    /// Reads the notes of the latest state of the pool.
+    pub fn update(&self, pool: P) {
+        let mut state = self.0.borrow_mut();
+        for event in state.pending.drain(..) {
+            pool.send(event);
+        }
+        state.pool = Some(pool);
+        for (_, waker) in state.wakers.drain() {
+            waker.wake();
+        }
+    }
+}
+impl<P: RelayPool> GameRelay for RelayPoolFeed<P> {
+    fn send(&self, event: nostr_minions::nostro2::NostrClientEvent) {
+        let mut state = self.0.borrow_mut();
+        match &event {
+            nostr_minions::nostro2::NostrClientEvent::Subscribe(_, subscription, _) => {
+                state.cursors.insert(subscription.clone(), 0);
+            }
+            nostr_minions::nostro2::NostrClientEvent::CloseSubscriptionEvent(_, subscription) => {
+                state.cursors.remove(subscription);
+                state.wakers.remove(subscription);
+            }
+            _ => {}
+        }
+        match &state.pool {
+            Some(pool) => {
+                pool.send(event);
+            }
+            None => state.pending.push(event),
+        }
+    }
+    fn poll_note(
+        &self,
+        subscription: &str,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<Option<nostr_minions::nostro2::NostrNote>> {
+        let mut state = self.0.borrow_mut();
+        let Some(&cursor) = state.cursors.get(subscription) else {
+            return Poll::Ready(None);
+        };
+        let note = state
+            .pool
+            .as_ref()
+            .and_then(|pool| pool.notes().get(cursor).cloned());
+        if let Some(note) = note {
+            state.cursors.insert(subscription.to_string(), cursor + 1);
+            return Poll::Ready(Some(note));
+        }
+        state
+            .wakers
+            .insert(subscription.to_string(), cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+/// [`GameRelay`] keeping events in memory, that answers subscriptions like a relay.
+///
+/// Every note published is kept, without checking ids or signatures, so the same event
+/// can be sent more than once like it would be by several relays.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryRelay(std::rc::Rc<std::cell::RefCell<MemoryRelayState>>);
+#[derive(Debug, Default)]
+struct MemoryRelayState {
+    notes: Vec<nostr_minions::nostro2::NostrNote>,
+    subscriptions: std::collections::HashMap<String, MemorySubscription>,
+}
+#[derive(Debug)]
+struct MemorySubscription {
+    filter: nostr_minions::nostro2::NostrSubscription,
+    queue: std::collections::VecDeque<nostr_minions::nostro2::NostrNote>,
+    waker: Option<std::task::Waker>,
+}
+impl MemoryRelay {
+    /// Stores a note and sends it to the open subscriptions it matches.
+    pub fn publish(&self, note: nostr_minions::nostro2::NostrNote) {
+        let mut state = self.0.borrow_mut();
+        for subscription in state.subscriptions.values_mut() {
+            if filter_matches(&subscription.filter, &note) {
+                subscription.queue.push_back(note.clone());
+                if let Some(waker) = subscription.waker.take() {
+                    waker.wake();
+                }
+            }
+        }
+        state.notes.push(note);
+    }
+    /// Filters of the open subscriptions.
+    #[must_use]
+    pub fn subscriptions(&self) -> Vec<nostr_minions::nostro2::NostrSubscription> {
+        self.0
+            .borrow()
+            .subscriptions
+            .values()
+            .map(|subscription| subscription.filter.clone())
+            .collect()
+    }
+}
+impl GameRelay for MemoryRelay {
+    fn send(&self, event: nostr_minions::nostro2::NostrClientEvent) {
+        match event {
+            nostr_minions::nostro2::NostrClientEvent::SendNoteEvent(_, note) => self.publish(note),
+            nostr_minions::nostro2::NostrClientEvent::Subscribe(_, id, filter) => {
+                let mut state = self.0.borrow_mut();
+                // Stored events are sent newest first, up to the limit of the filter
+                let mut stored = state
+                    .notes
+                    .iter()
+                    .filter(|note| filter_matches(&filter, note))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                stored.sort_by_key(|note| std::cmp::Reverse(note.created_at));
+                if let Some(limit) = filter.limit {
+                    stored.truncate(usize::try_from(limit).unwrap_or(usize::MAX));
+                }
+                state.subscriptions.insert(
+                    id,
+                    MemorySubscription {
+                        filter,
+                        queue: stored.into(),
+                        waker: None,
+                    },
+                );
+            }
+            nostr_minions::nostro2::NostrClientEvent::CloseSubscriptionEvent(_, id) => {
+                self.0.borrow_mut().subscriptions.remove(&id);
+            }
+            _ => {}
+        }
+    }
+    fn poll_note(
+        &self,
+        subscription: &str,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<Option<nostr_minions::nostro2::NostrNote>> {
+        let mut state = self.0.borrow_mut();
+        let Some(subscription) = state.subscriptions.get_mut(subscription) else {
+            return Poll::Ready(None);
+        };
+        if let Some(note) = subscription.queue.pop_front() {
+            return Poll::Ready(Some(note));
+        }
+        subscription.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+/// Whether a note matches a filter, as NIP-01 describes.
+fn filter_matches(
+    filter: &nostr_minions::nostro2::NostrSubscription,
+    note: &nostr_minions::nostro2::NostrNote,
+) -> bool {
+    let created_at = u64::try_from(note.created_at).unwrap_or_default();
+    let tags_match = filter.tags.iter().flatten().all(|(key, values)| {
+        // Keys without `#` are extensions, like NIP-50 search, that are not supported
+        key.strip_prefix('#').is_none_or(|name| {
+            note.tags.0.iter().any(|tag| {
+                tag.first().is_some_and(|tag_name| tag_name == name)
+                    && tag.get(1).is_some_and(|value| values.contains(value))
+            })
+        })
+    });
+    filter
+        .ids
+        .as_ref()
+        .is_none_or(|ids| note.id.as_ref().is_some_and(|id| ids.contains(id)))
+        && filter
+            .authors
+            .as_ref()
+            .is_none_or(|authors| authors.contains(&note.pubkey))
+        && filter
+            .kinds
+            .as_ref()
+            .is_none_or(|kinds| kinds.contains(&note.kind))
+        && filter.since.is_none_or(|since| created_at >= since)
+        && filter.until.is_none_or(|until| created_at <= until)
+        && tags_match
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_core::Stream;
+
+    /// Secret key of the author of the games published in the tests.
+    const ALICE: &str = "4cd8b6e9c5b8ac6f4bb3d9b1c4ab9bd33ed6fcc0cc5c7e1dfa2e7e3d5b1c2a11";
+
+    fn alice() -> nostro2_signer::keypair::NostrKeypair {
+        ALICE.parse().unwrap()
+    }
+    fn alice_pubkey() -> crate::rtr::NostrPubkey {
+        alice().public_key().parse().unwrap()
+    }
+    fn signed(mut note: nostr_minions::nostro2::NostrNote) -> nostr_minions::nostro2::NostrNote {
+        alice().sign_note(&mut note).unwrap();
+        note
+    }
+    fn published(white: &str, black: &str, moves: &str) -> nostr_minions::nostro2::NostrNote {
+        let game = format!(
+            "[Event \"Casual Game\"]\n[Date \"2025.01.04\"]\n[White \"{white}\"]\n[Black \"{black}\"]\n\n{moves}\n"
+        )
+        .parse::<crate::RookyGame>()
+        .unwrap();
+        signed(game.into())
+    }
+
+    fn poll_all<R: GameRelay + Unpin>(stream: &mut GameStream<R>) -> Vec<String> {
+        let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+        let mut players = Vec::new();
+        while let Poll::Ready(Some(entry)) = std::pin::Pin::new(&mut *stream).poll_next(&mut cx) {
+            assert_eq!(entry.origin, crate::idb::GameOrigin::Public);
+            assert_eq!(entry.note.id.as_ref(), Some(&entry.id));
+            players.push(crate::RookyGame::from(entry).white);
+        }
+        players
+    }
+
+    #[test]
+    fn test_stream_public_games() {
+        let relay = MemoryRelay::default();
+        let ruy_lopez = published("alice", "bob", "1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 *");
+        relay.publish(ruy_lopez.clone());
+        relay.publish(published("carol", "dave", "1. e4 c5 *"));
+        relay.publish(published(
+            "erin",
+            "frank",
+            "1. e4 e5 2. Nf3 Nc6 3. Bb5 Nf6 *",
+        ));
+        relay.publish(signed(nostr_minions::nostro2::NostrNote {
+            content: "Good game!".to_string(),
+            ..Default::default()
+        }));
+
+        let repository = GameRepository::new(relay.clone());
+        let mut games = repository.games(GameQuery::default().add_opening("Ruy Lopez"));
+        let mut players = poll_all(&mut games);
+        players.sort();
+        assert_eq!(players, vec!["alice", "erin"]);
+
+        // Sent again, like by a second relay, and a new game
+        relay.publish(ruy_lopez);
+        relay.publish(published("grace", "heidi", "1. e4 e5 2. Nf3 Nc6 3. Bb5 *"));
+        assert_eq!(poll_all(&mut games), vec!["grace"]);
+
+        let mut legacy = repository.games(
+            GameQuery::default()
+                .add_author(alice_pubkey())
+                .add_legacy_games(),
+        );
+        assert_eq!(poll_all(&mut legacy).len(), 4);
+        assert_eq!(relay.subscriptions().len(), 2);
+        drop(games);
+        drop(legacy);
+        assert!(relay.subscriptions().is_empty());
+    }
+
+    #[test]
+    fn test_skip_altered_games() {
+        let relay = MemoryRelay::default();
+        let original = published("alice", "bob", "1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 *");
+        let mut altered = original.clone();
+        altered.content = altered.content.replace("alice", "mallory");
+        let mut forged = altered.clone();
+        forged.serialize_id().unwrap();
+        let unsigned = nostr_minions::nostro2::NostrNote {
+            sig: None,
+            ..original.clone()
+        };
+        relay.publish(altered);
+        relay.publish(forged);
+        relay.publish(unsigned);
+
+        let repository = GameRepository::new(relay.clone());
+        let mut games = repository.games(GameQuery::default().add_opening("Ruy Lopez"));
+        assert!(poll_all(&mut games).is_empty());
+        relay.publish(original);
+        assert_eq!(poll_all(&mut games), vec!["alice"]);
+    }
+
+    #[test]
+    fn test_stream_annotated_versions() {
+        let relay = MemoryRelay::default();
+        let mut game = "1. e4 e5 *".parse::<crate::RookyGame>().unwrap();
+        let save = |game: &crate::RookyGame, saved_at: i64| {
+            signed(nostr_minions::nostro2::NostrNote {
+                created_at: saved_at,
+                ..crate::events::annotated_game_note(game, "open-games")
+            })
+        };
+        let first = save(&game, 1_000);
+        game.moves[1].comments_after.push("Symmetrical".to_string());
+        let second = save(&game, 2_000);
+        relay.publish(first.clone());
+        relay.publish(second.clone());
+
+        let repository = GameRepository::new(relay);
+        let mut stream = repository.games(
+            GameQuery::default()
+                .add_author(alice_pubkey())
+                .add_identifier("open-games"),
+        );
+        let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+        let mut entries = Vec::new();
+        while let Poll::Ready(Some(entry)) = std::pin::Pin::new(&mut stream).poll_next(&mut cx) {
+            entries.push(entry);
+        }
+        assert_eq!(entries.len(), 2);
+        assert!(entries.iter().all(|entry| entry.id == entries[0].id));
+        let latest = entries.pop().unwrap();
+        assert_eq!(latest.note, second);
+        assert_eq!(latest.history, vec![first]);
+    }
+
+    /// Relay pool holding the notes it is given, and the messages sent to it.
+    #[derive(Debug, Clone, Default)]
+    struct FakePool {
+        notes: Vec<nostr_minions::nostro2::NostrNote>,
+        sent: std::rc::Rc<std::cell::RefCell<Vec<nostr_minions::nostro2::NostrClientEvent>>>,
+    }
+    impl RelayPool for FakePool {
+        fn send(&self, event: nostr_minions::nostro2::NostrClientEvent) {
+            self.sent.borrow_mut().push(event);
+        }
+        fn notes(&self) -> &[nostr_minions::nostro2::NostrNote] {
+            &self.notes
+        }
+    }
+
+    #[test]
+    fn test_relay_pool_feed() {
+        struct Woken(std::sync::atomic::AtomicBool);
+        impl std::task::Wake for Woken {
+            fn wake(self: std::sync::Arc<Self>) {
+                self.0.store(true, std::sync::atomic::Ordering::SeqCst);
+            }
+        }
+        let woken = std::sync::Arc::new(Woken(std::sync::atomic::AtomicBool::new(false)));
+        let waker = std::task::Waker::from(woken.clone());
+        let mut cx = std::task::Context::from_waker(&waker);
+
+        let feed = RelayPoolFeed::default();
+        let repository = GameRepository::new(feed.clone());
+        let mut games = repository.games(GameQuery::default().add_opening("Ruy Lopez"));
+        assert_eq!(
+            std::pin::Pin::new(&mut games).poll_next(&mut cx),
+            Poll::Pending
+        );
+
+        // The subscription is sent with the first pool, which wakes the stream
+        let mut pool = FakePool::default();
+        pool.notes
+            .push(published("alice", "bob", "1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 *"));
+        pool.notes.push(published("carol", "dave", "1. e4 c5 *"));
+        feed.update(pool.clone());
+        assert!(woken.0.load(std::sync::atomic::Ordering::SeqCst));
+        assert!(matches!(
+            pool.sent.borrow().as_slice(),
+            [nostr_minions::nostro2::NostrClientEvent::Subscribe(_, id, _)] if id == games.subscription()
+        ));
+        assert_eq!(poll_all(&mut games), vec!["alice"]);
+
+        // Later states of the pool only add notes, the stream goes on from where it was
+        pool.notes
+            .push(published("erin", "frank", "1. e4 e5 2. Nf3 Nc6 3. Bb5 *"));
+        feed.update(pool.clone());
+        assert_eq!(poll_all(&mut games), vec!["erin"]);
+
+        // A new stream reads the notes received before it
+        let mut again = repository.games(GameQuery::default().add_opening("Ruy Lopez"));
+        assert_eq!(poll_all(&mut again), vec!["alice", "erin"]);
+
+        drop(games);
+        assert!(matches!(
+            pool.sent.borrow().last(),
+            Some(nostr_minions::nostro2::NostrClientEvent::CloseSubscriptionEvent(_, _))
+        ));
+        let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+        assert_eq!(feed.poll_note("closed", &mut cx), Poll::Ready(None));
+    }
+
+    #[test]
+    fn test_memory_relay_filters() {
+        let relay = MemoryRelay::default();
+        let first = published("alice", "bob", "1. e4 *");
+        let second = signed(nostr_minions::nostro2::NostrNote {
+            created_at: first.created_at + 60,
+            ..published("carol", "dave", "1. d4 *")
+        });
+        relay.publish(first.clone());
+        relay.publish(second.clone());
+
+        let filter = nostr_minions::nostro2::NostrSubscription {
+            kinds: Some(vec![crate::events::GAME_KIND]),
+            limit: Some(1),
+            ..Default::default()
+        };
+        let request = nostr_minions::nostro2::NostrClientEvent::from(&filter);
+        let nostr_minions::nostro2::NostrClientEvent::Subscribe(_, id, _) = &request else {
+            unreachable!()
+        };
+        let id = id.clone();
+        relay.send(request);
+        let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+        assert_eq!(relay.poll_note(&id, &mut cx), Poll::Ready(Some(second)));
+        assert_eq!(relay.poll_note(&id, &mut cx), Poll::Pending);
+
+        let mut tagged = nostr_minions::nostro2::NostrSubscription::default();
+        tagged.add_tag("#l", "alice");
+        assert!(filter_matches(&tagged, &first));
+        tagged.add_tag("#p", &alice().public_key());
+        assert!(!filter_matches(&tagged, &first));
+    }
+}
@@ -0,0 +1,136 @@
+//! The Rooky Tag Roster (RTR).
+//!
+//! Tags added by the protocol on top of the PGN Seven Tag Roster, tying a game to Nostr:
+//!
+//! - `[WhitePubkey "..."]` and `[BlackPubkey "..."]`: public keys of the players, written as
+//!   64 hex characters. `npub` encoded keys are accepted when reading.
+//! - `[Relays "wss://relay.one wss://relay.two"]`: space separated relays where the players
+//!   and the game can be found.
+//! - `[Client "..."]`: name of the application that published the game.
+//!
+//! All of them are optional, a game without them only has free text player names.
+
+/// Public key of a Nostr user.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct NostrPubkey([u8; 32]);
+impl NostrPubkey {
+    #[must_use]
+    pub const fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+    /// The key as a bech32 string starting with `npub`.
+    #[must_use]
+    pub fn npub(&self) -> String {
+        let hrp = bech32::Hrp::parse_unchecked("npub");
+        // Only data longer than the bech32 limit can fail to encode, a key never is.
+        bech32::encode::<bech32::Bech32>(hrp, &self.0).unwrap_or_default()
+    }
+}
+impl From<[u8; 32]> for NostrPubkey {
+    fn from(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+}
+impl std::str::FromStr for NostrPubkey {
+    type Err = crate::errors::ChessError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || crate::errors::ChessError::InvalidValue {
+            kind: "pubkey",
+            value: s.to_string(),
+        };
+        let bytes = if s.starts_with("npub1") {
+            let (hrp, data) = bech32::decode(s).map_err(|_| invalid())?;
+            if hrp.as_str() != "npub" {
+                return Err(invalid());
+            }
+            data
+        } else {
+            // `from_str_radix` would take a `+` sign as well.
+            if s.len() != 64 || !s.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+                return Err(invalid());
+            }
+            (0..s.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(&s[i..i + 2], 16))
+                .collect::<Result<Vec<u8>, _>>()
+                .map_err(|_| invalid())?
+        };
+        bytes.try_into().map(Self).map_err(|_| invalid())
+    }
+}
+impl std::fmt::Display for NostrPubkey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for byte in self.0 {
+            write!(f, "{byte:02x}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Address of a Nostr relay, a `wss://` or `ws://` URL.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct RelayUrl(String);
+impl RelayUrl {
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+impl std::str::FromStr for RelayUrl {
+    type Err = crate::errors::ChessError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let host = s
+            .strip_prefix("wss://")
+            .or_else(|| s.strip_prefix("ws://"))
+            .unwrap_or_default();
+        if host.is_empty() || s.contains(char::is_whitespace) || s.contains('"') {
+            return Err(crate::errors::ChessError::InvalidValue {
+                kind: "relay",
+                value: s.to_string(),
+            });
+        }
+        Ok(Self(s.to_string()))
+    }
+}
+impl std::fmt::Display for RelayUrl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+crate::serialization::serde_as_string!(NostrPubkey, RelayUrl);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEX: &str = "3bf0c63fcb93463407af97a5e5ee64fa883d107ef9e558472c4eb9aaaefa459d";
+    const NPUB: &str = "npub180cvv07tjdrrgpa0j7j7tmnyl2yr6yr7l8j4s3evf6u64th6gkwsyjh6w6";
+
+    #[test]
+    fn test_nostr_pubkey() {
+        let from_hex = HEX.parse::<NostrPubkey>().unwrap();
+        let from_npub = NPUB.parse::<NostrPubkey>().unwrap();
+        assert_eq!(from_hex, from_npub);
+        assert_eq!(from_npub.to_string(), HEX);
+        assert_eq!(from_hex.npub(), NPUB);
+
+        assert!("3bf0c6".parse::<NostrPubkey>().is_err());
+        assert!(HEX.replace('3', "z").parse::<NostrPubkey>().is_err());
+        assert!(HEX.replacen("3b", "+b", 1).parse::<NostrPubkey>().is_err());
+        assert!("npub1qqqqqq".parse::<NostrPubkey>().is_err());
+    }
+
+    #[test]
+    fn test_relay_url() {
+        assert_eq!(
+            "wss://relay.damus.io".parse::<RelayUrl>().unwrap().as_str(),
+            "wss://relay.damus.io"
+        );
+        assert!("ws://localhost:7000".parse::<RelayUrl>().is_ok());
+        assert!("https://relay.damus.io".parse::<RelayUrl>().is_err());
+        assert!("wss://".parse::<RelayUrl>().is_err());
+    }
+}
@@ -0,0 +1,84 @@
+//! Helpers for the `serde` representation of games.
+//!
+//! Values that have a PGN notation, like results, time controls or pubkeys,
+//! are serialized as that same text so the JSON reads like the PGN tags.
+
+/// Implements `Serialize` and `Deserialize` through `Display` and `FromStr`.
+macro_rules! serde_as_string {
+    ($($ty:ty),+ $(,)?) => {$(
+        impl serde::Serialize for $ty {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_str(self)
+            }
+        }
+        impl<'de> serde::Deserialize<'de> for $ty {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let text = <std::borrow::Cow<'de, str> as serde::Deserialize>::deserialize(deserializer)?;
+                text.parse().map_err(serde::de::Error::custom)
+            }
+        }
+    )+};
+}
+pub(crate) use serde_as_string;
+
+/// Like [`serde_as_string`] for free text tags, which are read back as written.
+///
+/// This matches how the tag is read from PGN, so a game is the same whether it comes
+/// from JSON or PGN.
+macro_rules! serde_as_named {
+    ($($ty:ty),+ $(,)?) => {$(
+        impl serde::Serialize for $ty {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_str(self)
+            }
+        }
+        impl<'de> serde::Deserialize<'de> for $ty {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                <String as serde::Deserialize>::deserialize(deserializer).map(Self::Named)
+            }
+        }
+    )+};
+}
+pub(crate) use serde_as_named;
+
+/// Numeric annotation glyphs as their numbers, `$14` is `14`.
+pub mod nags {
+    use serde::{Deserialize, Serialize};
+
+    pub fn serialize<S: serde::Serializer>(
+        nags: &[pgn_reader::Nag],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        nags.iter()
+            .map(|nag| nag.0)
+            .collect::<Vec<_>>()
+            .serialize(serializer)
+    }
+    pub fn deserialize<'de, D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<pgn_reader::Nag>, D::Error> {
+        let nags = Vec::<u8>::deserialize(deserializer)?;
+        Ok(nags.into_iter().map(pgn_reader::Nag).collect())
+    }
+}
+
+/// Optional durations as whole milliseconds.
+pub mod millis {
+    use serde::{Deserialize, Serialize};
+
+    #[allow(clippy::ref_option)]
+    pub fn serialize<S: serde::Serializer>(
+        duration: &Option<std::time::Duration>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        duration
+            .map(|duration| u64::try_from(duration.as_millis()).unwrap_or(u64::MAX))
+            .serialize(serializer)
+    }
+    pub fn deserialize<'de, D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<std::time::Duration>, D::Error> {
+        let millis = Option::<u64>::deserialize(deserializer)?;
+        Ok(millis.map(std::time::Duration::from_millis))
+    }
+}
@@ -0,0 +1,427 @@
+//! Private game sharing, with NIP-59 gift wraps encrypted with NIP-44.
+//!
+//! A game shared privately goes through three layers:
+//!
+//! - The rumor: the game event, see [`crate::events`], by the sender and never signed, so
+//!   it can not be shown to anyone else as proof of what was sent.
+//! - The seal: a kind 13 event signed by the sender, holding the rumor encrypted for the
+//!   recipient.
+//! - The gift wrap: a kind 1059 event signed by a key used only once, holding the seal
+//!   encrypted for the recipient and a `p` tag so relays deliver it.
+//!
+//! Relays only see that someone received a gift wrap, not who sent it or what it holds.
+//! Seals and gift wraps are dated up to two days in the past, so their time does not
+//! link them to each other either.
+//!
+//! Signing and encryption go through [`GameSigner`], implemented for keys held by the
+//! application, [`NostrKeypair`](nostro2_signer::keypair::NostrKeypair), and for browser
+//! extensions following NIP-07, [`ExtensionSigner`].
+
+// Signers run on the thread of the page, the promises of extensions never leave it.
+#![allow(clippy::future_not_send)]
+
+/// Kind of the seal holding the encrypted rumor.
+pub const SEAL_KIND: u32 = 13;
+/// Kind of the gift wrap holding the encrypted seal.
+pub const GIFT_WRAP_KIND: u32 = 1059;
+/// How far in the past seals and gift wraps can be dated, two days as NIP-59 suggests.
+const TIMESTAMP_JITTER: u64 = 2 * 24 * 60 * 60;
+
+/// Signs and encrypts notes for the user, with a key they hold or through an extension.
+///
+/// Extensions only answer asynchronously, so every operation is a future.
+pub trait GameSigner {
+    /// Public key of the user, as hex.
+    fn public_key(
+        &self,
+    ) -> impl std::future::Future<Output = Result<String, crate::errors::ChessError>>;
+    /// Sets the pubkey, id and signature of a note.
+    fn sign_note(
+        &self,
+        note: &mut nostr_minions::nostro2::NostrNote,
+    ) -> impl std::future::Future<Output = Result<(), crate::errors::ChessError>>;
+    /// Encrypts text for a peer with NIP-44.
+    fn nip44_encrypt(
+        &self,
+        plaintext: &str,
+        peer_pubkey: &str,
+    ) -> impl std::future::Future<Output = Result<String, crate::errors::ChessError>>;
+    /// Decrypts NIP-44 text from a peer.
+    fn nip44_decrypt(
+        &self,
+        ciphertext: &str,
+        peer_pubkey: &str,
+    ) -> impl std::future::Future<Output = Result<String, crate::errors::ChessError>>;
+}
+
+impl GameSigner for nostro2_signer::keypair::NostrKeypair {
+    async fn public_key(&self) -> Result<String, crate::errors::ChessError> {
+        Ok(self.public_key())
+    }
+    async fn sign_note(
+        &self,
+        note: &mut nostr_minions::nostro2::NostrNote,
+    ) -> Result<(), crate::errors::ChessError> {
+        Self::sign_note(self, note).map_err(signer_error)
+    }
+    async fn nip44_encrypt(
+        &self,
+        plaintext: &str,
+        peer_pubkey: &str,
+    ) -> Result<String, crate::errors::ChessError> {
+        nostro2_signer::nostro2_nips::Nip44::nip_44_encrypt(self, plaintext, peer_pubkey)
+            .map(std::borrow::Cow::into_owned)
+            .map_err(signer_error)
+    }
+    async fn nip44_decrypt(
+        &self,
+        ciphertext: &str,
+        peer_pubkey: &str,
+    ) -> Result<String, crate::errors::ChessError> {
+        nostro2_signer::nostro2_nips::Nip44::nip_44_decrypt(self, ciphertext, peer_pubkey)
+            .map(std::borrow::Cow::into_owned)
+            .map_err(signer_error)
+    }
+}
+
+/// Browser extension holding the keys of the user, through the `window.nostr` object of
+/// NIP-07.
+///
+/// The extension has to support NIP-44, every call may ask the user for permission.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExtensionSigner;
+impl ExtensionSigner {
+    /// Whether an extension is installed in this browser.
+    #[must_use]
+    pub fn is_available() -> bool {
+        Self::object(&[]).is_ok()
+    }
+    /// The `window.nostr` object, or one of its members like `window.nostr.nip44`.
+    fn object(path: &[&str]) -> Result<web_sys::wasm_bindgen::JsValue, crate::errors::ChessError> {
+        let window = web_sys::window()
+            .ok_or_else(|| crate::errors::ChessError::Signer("No browser window".to_string()))?;
+        let mut object = web_sys::wasm_bindgen::JsValue::from(window);
+        for name in std::iter::once(&"nostr").chain(path) {
+            object = web_sys::js_sys::Reflect::get(&object, &(*name).into())
+                .ok()
+                .filter(|member| !member.is_undefined() && !member.is_null())
+                .ok_or_else(|| {
+                    crate::errors::ChessError::Signer(format!("The extension has no {name}"))
+                })?;
+        }
+        Ok(object)
+    }
+    /// Calls a method of the extension and waits for the promise it returns.
+    async fn call(
+        path: &[&str],
+        method: &str,
+        arguments: &[web_sys::wasm_bindgen::JsValue],
+    ) -> Result<web_sys::wasm_bindgen::JsValue, crate::errors::ChessError> {
+        use web_sys::wasm_bindgen::JsCast;
+        let object = Self::object(path)?;
+        let function = web_sys::js_sys::Reflect::get(&object, &method.into())
+            .map_err(js_error)?
+            .dyn_into::<web_sys::js_sys::Function>()
+            .map_err(js_error)?;
+        let arguments = arguments.iter().collect::<web_sys::js_sys::Array>();
+        let promise = function.apply(&object, &arguments).map_err(js_error)?;
+        wasm_bindgen_futures::JsFuture::from(web_sys::js_sys::Promise::resolve(&promise))
+            .await
+            .map_err(js_error)
+    }
+    async fn call_string(
+        path: &[&str],
+        method: &str,
+        arguments: &[web_sys::wasm_bindgen::JsValue],
+    ) -> Result<String, crate::errors::ChessError> {
+        Self::call(path, method, arguments)
+            .await?
+            .as_string()
+            .ok_or_else(|| {
+                crate::errors::ChessError::Signer(format!("{method} did not return a string"))
+            })
+    }
+}
+impl GameSigner for ExtensionSigner {
+    async fn public_key(&self) -> Result<String, crate::errors::ChessError> {
+        Self::call_string(&[], "getPublicKey", &[]).await
+    }
+    async fn sign_note(
+        &self,
+        note: &mut nostr_minions::nostro2::NostrNote,
+    ) -> Result<(), crate::errors::ChessError> {
+        use serde::Serialize;
+        let event = note
+            .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+            .map_err(|error| crate::errors::ChessError::Signer(error.to_string()))?;
+        let signed = Self::call(&[], "signEvent", &[event]).await?;
+        *note = serde_wasm_bindgen::from_value(signed)
+            .map_err(|error| crate::errors::ChessError::Signer(error.to_string()))?;
+        Ok(())
+    }
+    async fn nip44_encrypt(
+        &self,
+        plaintext: &str,
+        peer_pubkey: &str,
+    ) -> Result<String, crate::errors::ChessError> {
+        Self::call_string(
+            &["nip44"],
+            "encrypt",
+            &[peer_pubkey.into(), plaintext.into()],
+        )
+        .await
+    }
+    async fn nip44_decrypt(
+        &self,
+        ciphertext: &str,
+        peer_pubkey: &str,
+    ) -> Result<String, crate::errors::ChessError> {
+        Self::call_string(
+            &["nip44"],
+            "decrypt",
+            &[peer_pubkey.into(), ciphertext.into()],
+        )
+        .await
+    }
+}
+
+/// Gift wraps a game for each recipient, ready to be sent to relays.
+///
+/// Senders that want to keep a copy of what they sent add themselves to the recipients.
+///
+/// # Errors
+///
+/// Returns [`ChessError::Signer`](crate::errors::ChessError::Signer) if the signer fails
+/// to sign or encrypt.
+pub async fn gift_wrap_game(
+    signer: &impl GameSigner,
+    game: &crate::RookyGame,
+    recipients: &[crate::rtr::NostrPubkey],
+) -> Result<Vec<nostr_minions::nostro2::NostrNote>, crate::errors::ChessError> {
+    let mut rumor = nostr_minions::nostro2::NostrNote::from(game.clone());
+    rumor.pubkey = signer.public_key().await?;
+    rumor.serialize_id().map_err(signer_error)?;
+    let rumor = note_json(&rumor)?;
+
+    let mut wraps = Vec::with_capacity(recipients.len());
+    for recipient in recipients {
+        let recipient = recipient.to_string();
+        let mut seal = nostr_minions::nostro2::NostrNote {
+            kind: SEAL_KIND,
+            content: signer.nip44_encrypt(&rumor, &recipient).await?,
+            created_at: past_timestamp(),
+            ..Default::default()
+        };
+        signer.sign_note(&mut seal).await?;
+
+        let wrapper = <nostro2_signer::keypair::NostrKeypair as nostr_minions::nostro2::NostrSigner>::generate(false);
+        let mut wrap = nostr_minions::nostro2::NostrNote {
+            kind: GIFT_WRAP_KIND,
+            content: wrapper
+                .nip44_encrypt(&note_json(&seal)?, &recipient)
+                .await?,
+            created_at: past_timestamp(),
+            ..Default::default()
+        };
+        wrap.tags.add_pubkey_tag(&recipient, None);
+        GameSigner::sign_note(&wrapper, &mut wrap).await?;
+        wraps.push(wrap);
+    }
+    Ok(wraps)
+}
+
+/// Opens a gift wrap received by the user, giving the game inside with the
+/// [`Received`](crate::idb::GameOrigin::Received) origin.
+///
+/// The entry holds the rumor, the game event by the sender, and is keyed like a public
+/// event would be, so the same game sent to several of the user's devices is stored once.
+///
+/// # Errors
+///
+/// - [`ChessError::InvalidValue`](crate::errors::ChessError::InvalidValue) if the note is
+///   not a gift wrap, a signature is invalid, or the seal was signed by someone other than
+///   the author of the game.
+/// - [`ChessError::Signer`](crate::errors::ChessError::Signer) if the signer can not
+///   decrypt it, like when it was sent to someone else.
+/// - Any error of the PGN if the rumor is not a game.
+pub async fn unwrap_game(
+    signer: &impl GameSigner,
+    wrap: &nostr_minions::nostro2::NostrNote,
+) -> Result<crate::idb::RookyGameEntry, crate::errors::ChessError> {
+    let invalid = |value: &str| crate::errors::ChessError::InvalidValue {
+        kind: "gift wrap",
+        value: value.to_string(),
+    };
+    if wrap.kind != GIFT_WRAP_KIND {
+        return Err(invalid(&format!("kind {}", wrap.kind)));
+    }
+    if !crate::events::verify_note(wrap) {
+        return Err(invalid("signature of the wrap"));
+    }
+    let seal = parse_note(&signer.nip44_decrypt(&wrap.content, &wrap.pubkey).await?)?;
+    if seal.kind != SEAL_KIND || !crate::events::verify_note(&seal) {
+        return Err(invalid("seal"));
+    }
+    let mut rumor = parse_note(&signer.nip44_decrypt(&seal.content, &seal.pubkey).await?)?;
+    // Only the seal is signed, the rumor could otherwise claim any author
+    if rumor.pubkey != seal.pubkey {
+        return Err(invalid("author of the game"));
+    }
+    rumor.serialize_id().map_err(signer_error)?;
+    crate::RookyGame::try_from(rumor.clone())?;
+    Ok(crate::idb::RookyGameEntry::new(
+        rumor,
+        crate::idb::GameOrigin::Received,
+    ))
+}
+
+/// A time up to [`TIMESTAMP_JITTER`] in the past.
+fn past_timestamp() -> i64 {
+    let offset = rand::Rng::gen_range(&mut rand::thread_rng(), 0..TIMESTAMP_JITTER);
+    chrono::Utc::now().timestamp() - i64::try_from(offset).unwrap_or_default()
+}
+fn note_json(
+    note: &nostr_minions::nostro2::NostrNote,
+) -> Result<String, crate::errors::ChessError> {
+    serde_json::to_string(note)
+        .map_err(|error| crate::errors::ChessError::Signer(error.to_string()))
+}
+fn parse_note(json: &str) -> Result<nostr_minions::nostro2::NostrNote, crate::errors::ChessError> {
+    json.parse()
+        .map_err(|_| crate::errors::ChessError::InvalidValue {
+            kind: "gift wrap",
+            value: "content".to_string(),
+        })
+}
+fn signer_error(error: impl std::fmt::Display) -> crate::errors::ChessError {
+    crate::errors::ChessError::Signer(error.to_string())
+}
+fn js_error(error: impl std::fmt::Debug) -> crate::errors::ChessError {
+    crate::errors::ChessError::Signer(format!("{error:?}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::FutureExt;
+
+    const GAME: &str = r#"[Event "Training"]
+[Date "2025.01.04"]
+[White "alice"]
+[Black "coach"]
+[Result "0-1"]
+
+1. f3 e5 2. g4 Qh4# 0-1
+"#;
+
+    fn keypair() -> nostro2_signer::keypair::NostrKeypair {
+        <nostro2_signer::keypair::NostrKeypair as nostr_minions::nostro2::NostrSigner>::generate(
+            false,
+        )
+    }
+    fn pubkey(keypair: &nostro2_signer::keypair::NostrKeypair) -> crate::rtr::NostrPubkey {
+        keypair.public_key().parse().unwrap()
+    }
+
+    #[test]
+    fn test_share_game_privately() {
+        let (alice, coach, teammate) = (keypair(), keypair(), keypair());
+        let game = GAME.parse::<crate::RookyGame>().unwrap();
+        let wraps = gift_wrap_game(&alice, &game, &[pubkey(&coach), pubkey(&teammate)])
+            .now_or_never()
+            .unwrap()
+            .unwrap();
+        assert_eq!(wraps.len(), 2);
+        for wrap in &wraps {
+            assert_eq!(wrap.kind, GIFT_WRAP_KIND);
+            assert_ne!(wrap.pubkey, alice.public_key());
+            assert!(crate::events::verify_note(wrap));
+            assert!(!wrap.content.contains("[White \"alice\"]"));
+            assert!(wrap.created_at <= chrono::Utc::now().timestamp());
+        }
+        assert_eq!(
+            wraps[0].tags.first_tagged_pubkey(),
+            Some(coach.public_key())
+        );
+
+        let entry = unwrap_game(&coach, &wraps[0])
+            .now_or_never()
+            .unwrap()
+            .unwrap();
+        assert_eq!(entry.origin, crate::idb::GameOrigin::Received);
+        assert_eq!(entry.note.pubkey, alice.public_key());
+        assert_eq!(entry.note.kind, crate::events::GAME_KIND);
+        assert!(entry.note.sig.is_none());
+        assert_eq!(
+            crate::RookyGame::from(entry.clone()).to_pgn(),
+            game.to_pgn()
+        );
+        let copy = unwrap_game(&teammate, &wraps[1])
+            .now_or_never()
+            .unwrap()
+            .unwrap();
+        assert_eq!(copy.id, entry.id);
+
+        let mut redated = wraps[0].clone();
+        redated.created_at += 1;
+        redated.serialize_id().unwrap();
+        let error = unwrap_game(&coach, &redated)
+            .now_or_never()
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid gift wrap: signature of the wrap"
+        );
+
+        let error = unwrap_game(&teammate, &wraps[0])
+            .now_or_never()
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(error.code(), "signer");
+        let error = unwrap_game(&coach, &entry.note)
+            .now_or_never()
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(error.code(), "invalid_value");
+    }
+
+    #[test]
+    fn test_reject_forged_author() {
+        let (alice, mallory, coach) = (keypair(), keypair(), keypair());
+        let mut rumor =
+            nostr_minions::nostro2::NostrNote::from(GAME.parse::<crate::RookyGame>().unwrap());
+        rumor.pubkey = alice.public_key();
+        let mut seal = nostr_minions::nostro2::NostrNote {
+            kind: SEAL_KIND,
+            content: GameSigner::nip44_encrypt(
+                &mallory,
+                &note_json(&rumor).unwrap(),
+                &coach.public_key(),
+            )
+            .now_or_never()
+            .unwrap()
+            .unwrap(),
+            ..Default::default()
+        };
+        mallory.sign_note(&mut seal).unwrap();
+        let mut wrap = nostr_minions::nostro2::NostrNote {
+            kind: GIFT_WRAP_KIND,
+            content: GameSigner::nip44_encrypt(
+                &mallory,
+                &note_json(&seal).unwrap(),
+                &coach.public_key(),
+            )
+            .now_or_never()
+            .unwrap()
+            .unwrap(),
+            ..Default::default()
+        };
+        mallory.sign_note(&mut wrap).unwrap();
+        let error = unwrap_game(&coach, &wrap)
+            .now_or_never()
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(error.to_string(), "Invalid gift wrap: author of the game");
+    }
+}
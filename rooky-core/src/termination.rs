@@ -0,0 +1,197 @@
+//! How a game ended, according to its moves.
+//!
+//! The `Result` tag of a PGN is only what was recorded. [`GameEnd`] is read from the board
+//! instead: the final position of the main line and how often it was repeated, so it can
+//! fill in the result of a game or flag one that disagrees with its moves.
+//!
+//! Checkmate, stalemate, insufficient material, the rules of a variant, fivefold repetition
+//! and the 75-move rule end the game by themselves. Threefold repetition and the 50-move
+//! rule only allow a player to claim a draw, the game goes on until someone does.
+
+use shakmaty::Position;
+
+/// A rule that ends the game, or allows a player to claim a draw, in the final position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GameEnd {
+    Checkmate {
+        winner: shakmaty::Color,
+    },
+    Stalemate,
+    /// Neither side has the material to checkmate.
+    InsufficientMaterial,
+    /// A rule of the variant ended the game, like a king reaching the hill in King of the Hill.
+    Variant(shakmaty::Outcome),
+    /// The same position appeared five times.
+    FivefoldRepetition,
+    /// 75 moves by each side without a capture or a pawn move.
+    SeventyFiveMoveRule,
+    /// The same position appeared three times, a draw can be claimed.
+    ThreefoldRepetition,
+    /// 50 moves by each side without a capture or a pawn move, a draw can be claimed.
+    FiftyMoveRule,
+}
+impl GameEnd {
+    /// Finds how the game ends in `position`, that appeared `repetitions` times in the game.
+    ///
+    /// Rules that end the game come before draws that can only be claimed, so a checkmate
+    /// delivered on the fiftieth move is still a checkmate.
+    #[must_use]
+    pub fn detect(
+        position: &shakmaty::variant::VariantPosition,
+        repetitions: usize,
+    ) -> Option<Self> {
+        if let Some(outcome) = position.variant_outcome() {
+            return Some(Self::Variant(outcome));
+        }
+        if position.legal_moves().is_empty() {
+            return Some(if position.is_check() {
+                Self::Checkmate {
+                    winner: !position.turn(),
+                }
+            } else {
+                Self::Stalemate
+            });
+        }
+        if position.is_insufficient_material() {
+            return Some(Self::InsufficientMaterial);
+        }
+        if repetitions >= 5 {
+            return Some(Self::FivefoldRepetition);
+        }
+        if position.halfmoves() >= 150 {
+            return Some(Self::SeventyFiveMoveRule);
+        }
+        if repetitions >= 3 {
+            return Some(Self::ThreefoldRepetition);
+        }
+        if position.halfmoves() >= 100 {
+            return Some(Self::FiftyMoveRule);
+        }
+        None
+    }
+    /// Result of the game when it ends this way, claimed draws included.
+    #[must_use]
+    pub const fn outcome(self) -> shakmaty::Outcome {
+        match self {
+            Self::Checkmate { winner } => shakmaty::Outcome::Decisive { winner },
+            Self::Variant(outcome) => outcome,
+            _ => shakmaty::Outcome::Draw,
+        }
+    }
+    /// Whether the game is over, rather than a draw that can be claimed.
+    #[must_use]
+    pub const fn is_final(self) -> bool {
+        !matches!(self, Self::ThreefoldRepetition | Self::FiftyMoveRule)
+    }
+}
+impl std::fmt::Display for GameEnd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Checkmate { winner } => write!(f, "Checkmate, {winner} wins"),
+            Self::Stalemate => write!(f, "Stalemate"),
+            Self::InsufficientMaterial => write!(f, "Insufficient material"),
+            Self::Variant(outcome) => write!(f, "Variant end, {outcome}"),
+            Self::FivefoldRepetition => write!(f, "Fivefold repetition"),
+            Self::SeventyFiveMoveRule => write!(f, "75-move rule"),
+            Self::ThreefoldRepetition => write!(f, "Threefold repetition"),
+            Self::FiftyMoveRule => write!(f, "50-move rule"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(fen: Option<&str>, moves: &[&str]) -> crate::RookyGame {
+        let mut game = crate::RookyGame::default();
+        if let Some(fen) = fen {
+            game = game.add_starting_position(fen.parse().unwrap()).unwrap();
+        }
+        for san in moves {
+            game.play(&san.parse().unwrap()).unwrap();
+        }
+        game
+    }
+
+    #[test]
+    fn test_detect_game_end() {
+        let mut mate = play(None, &["f3", "e5", "g4", "Qh4"]);
+        assert_eq!(
+            mate.game_end().unwrap(),
+            Some(GameEnd::Checkmate {
+                winner: shakmaty::Color::Black
+            })
+        );
+        assert!(mate.check_result().is_ok());
+        mate.detect_outcome().unwrap();
+        assert_eq!(mate.outcome.to_string(), "0-1");
+        assert_eq!(
+            mate.termination,
+            Some(crate::pgn_standards::PgnTermination::Normal)
+        );
+
+        let stalemate = play(Some("7k/8/4Q3/6K1/8/8/8/8 w - - 0 1"), &["Qf7"]);
+        assert_eq!(stalemate.game_end().unwrap(), Some(GameEnd::Stalemate));
+        let material = play(Some("8/8/8/4k3/8/8/3n4/4K3 w - - 0 1"), &["Kxd2"]);
+        assert_eq!(
+            material.game_end().unwrap(),
+            Some(GameEnd::InsufficientMaterial)
+        );
+        let fifty = play(Some("8/8/8/4k3/8/8/3R4/4K3 w - - 99 80"), &["Rd3"]);
+        assert_eq!(fifty.game_end().unwrap(), Some(GameEnd::FiftyMoveRule));
+        let seventy_five = play(Some("8/8/8/4k3/8/8/3R4/4K3 w - - 149 80"), &["Rd3"]);
+        assert_eq!(
+            seventy_five.game_end().unwrap(),
+            Some(GameEnd::SeventyFiveMoveRule)
+        );
+        assert!(play(None, &["e4", "e5"]).game_end().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_detect_repetitions() {
+        const SHUFFLE: [&str; 4] = ["Nf3", "Nf6", "Ng1", "Ng8"];
+        let mut threefold = play(None, &SHUFFLE.repeat(2));
+        assert_eq!(
+            threefold.detect_outcome().unwrap(),
+            Some(GameEnd::ThreefoldRepetition)
+        );
+        assert!(!threefold.outcome.is_finished());
+        assert!(threefold.termination.is_none());
+
+        let mut fivefold = play(None, &SHUFFLE.repeat(4));
+        assert_eq!(
+            fivefold.detect_outcome().unwrap(),
+            Some(GameEnd::FivefoldRepetition)
+        );
+        assert_eq!(fivefold.outcome.to_string(), "1/2-1/2");
+    }
+
+    #[test]
+    fn test_flag_result_mismatch() {
+        let mate = play(None, &["f3", "e5", "g4", "Qh4"]);
+        let error = mate
+            .add_result(
+                shakmaty::Outcome::Decisive {
+                    winner: shakmaty::Color::White,
+                }
+                .into(),
+            )
+            .check_result()
+            .unwrap_err();
+        assert_eq!(error.code(), "result_mismatch");
+
+        // A draw by agreement after a repetition is fine, as is White resigning.
+        let shuffle = play(
+            None,
+            &["Nf3", "Nf6", "Ng1", "Ng8", "Nf3", "Nf6", "Ng1", "Ng8"],
+        );
+        let resigned = shuffle.add_result(
+            shakmaty::Outcome::Decisive {
+                winner: shakmaty::Color::Black,
+            }
+            .into(),
+        );
+        assert!(resigned.check_result().is_ok());
+    }
+}